@@ -0,0 +1,62 @@
+//! Compares the exact and the Barnes-Hut repulsion on graphs of growing size.
+//!
+//! Run with `cargo bench`.
+
+use force_graph::{EdgeData, ForceGraph, NodeData, SimulationParameters};
+use std::time::{Duration, Instant};
+
+// Steps of 0.02 seconds, like the ones the controller takes each frame
+const STEPS: u32 = 200;
+const DT: f32 = 0.02;
+
+// A ring with a few chords, with the nodes spread on a spiral
+fn graph(nodes: usize, barnes_hut: bool) -> ForceGraph {
+    let parameters = SimulationParameters {
+        barnes_hut_threshold: if barnes_hut { 0 } else { usize::MAX },
+        ..Default::default()
+    };
+    let mut graph = <ForceGraph>::new(parameters);
+    let indices: Vec<_> = (0..nodes)
+        .map(|i| {
+            let angle = i as f32 * 2.4;
+            let radius = 20.0 * (i as f32).sqrt();
+            graph.add_node(NodeData {
+                x: radius * angle.cos(),
+                y: radius * angle.sin(),
+                ..Default::default()
+            })
+        })
+        .collect();
+    for i in 0..nodes {
+        graph.add_edge(indices[i], indices[(i + 1) % nodes], EdgeData::default());
+        if i % 25 == 0 {
+            graph.add_edge(
+                indices[i],
+                indices[(i + nodes / 2) % nodes],
+                EdgeData::default(),
+            );
+        }
+    }
+    graph
+}
+
+fn time(nodes: usize, barnes_hut: bool) -> Duration {
+    let mut graph = graph(nodes, barnes_hut);
+    let start = Instant::now();
+    for _ in 0..STEPS {
+        graph.update(DT);
+    }
+    start.elapsed() / STEPS
+}
+
+fn main() {
+    println!("{:>6} {:>14} {:>14}", "nodes", "exact", "barnes-hut");
+    for nodes in [25, 50, 100, 150, 200] {
+        println!(
+            "{:>6} {:>14?} {:>14?}",
+            nodes,
+            time(nodes, false),
+            time(nodes, true)
+        );
+    }
+}
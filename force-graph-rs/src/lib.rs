@@ -5,16 +5,27 @@ use petgraph::{
 
 use std::collections::BTreeSet;
 
+mod quadtree;
+use quadtree::QuadTree;
+
 pub type DefaultNodeIdx = NodeIndex<u8>;
 
 /// Parameters to control the simulation of the force graph.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct SimulationParameters {
     pub force_charge: f32,
     pub force_spring: f32,
     pub force_max: f32,
     pub node_speed: f32,
     pub damping_factor: f32,
+    /// The point every node is pulled towards.
+    pub center_x: f32,
+    pub center_y: f32,
+    /// Accuracy of the Barnes-Hut approximation, lower is more accurate but slower.
+    pub theta: f32,
+    /// With more nodes than this the repulsion is approximated with a Barnes-Hut quadtree,
+    /// instead of being computed between every pair of nodes.
+    pub barnes_hut_threshold: usize,
 }
 
 impl Default for SimulationParameters {
@@ -25,6 +36,10 @@ impl Default for SimulationParameters {
             force_max: 280.0,
             node_speed: 7000.0,
             damping_factor: 0.95,
+            center_x: -200.0,
+            center_y: 0.0,
+            theta: 0.9,
+            barnes_hut_threshold: 100,
         }
     }
 }
@@ -183,6 +198,27 @@ impl<UserNodeData, UserEdgeData> ForceGraph<UserNodeData, UserEdgeData> {
         (node.data.x, node.data.y)
     }
 
+    /// Moves a node to the given position, forgetting its current velocity.
+    pub fn set_node_position(&mut self, idx: DefaultNodeIdx, x: f32, y: f32) {
+        let node = &mut self.graph[idx];
+        node.data.x = x;
+        node.data.y = y;
+        node.vx = 0.0;
+        node.vy = 0.0;
+    }
+
+    /// Fixes a node to its current position, or releases it.
+    ///
+    /// The node starts again from rest, whatever was accumulated on it before is dropped.
+    pub fn set_anchor(&mut self, idx: DefaultNodeIdx, is_anchor: bool) {
+        let node = &mut self.graph[idx];
+        node.data.is_anchor = is_anchor;
+        node.vx = 0.0;
+        node.vy = 0.0;
+        node.ax = 0.0;
+        node.ay = 0.0;
+    }
+
     /// Applies the next step of the force graph simulation.
     ///
     /// The number of seconds that have elapsed since the previous update must be calculated and
@@ -192,15 +228,27 @@ impl<UserNodeData, UserEdgeData> ForceGraph<UserNodeData, UserEdgeData> {
             return;
         }
 
-        let center_x = -200.0;
-        let center_y = 0.0;
+        if self.node_indices.len() > self.parameters.barnes_hut_threshold {
+            self.update_barnes_hut(dt);
+        } else {
+            self.update_exact(dt);
+        }
+    }
+
+    // Repulsion computed between every pair of nodes, O(n^2)
+    fn update_exact(&mut self, dt: f32) {
+        let center_x = self.parameters.center_x;
+        let center_y = self.parameters.center_y;
 
         for (n1_idx_i, n1_idx) in self.node_indices.iter().enumerate() {
+            // Anchors never move, forces on them would only pile up until they are released
             let mut edges = self.graph.neighbors(*n1_idx).detach();
             while let Some(n2_idx) = edges.next_node(&self.graph) {
                 let (n1, n2) = self.graph.index_twice_mut(*n1_idx, n2_idx);
-                let f = attract_nodes(n1, n2, &self.parameters);
-                n1.apply_force(f.0, f.1, dt, &self.parameters);
+                if !n1.data.is_anchor {
+                    let f = attract_nodes(n1, n2, &self.parameters);
+                    n1.apply_force(f.0, f.1, dt, &self.parameters);
+                }
             }
 
             for n2_idx in self.node_indices.iter().skip(n1_idx_i + 1) {
@@ -223,6 +271,62 @@ impl<UserNodeData, UserEdgeData> ForceGraph<UserNodeData, UserEdgeData> {
         }
     }
 
+    // Repulsion approximated with a quadtree built once per step, O(n log n)
+    fn update_barnes_hut(&mut self, dt: f32) {
+        let center_x = self.parameters.center_x;
+        let center_y = self.parameters.center_y;
+
+        let bodies: Vec<(f32, f32, f32)> = self
+            .node_indices
+            .iter()
+            .map(|idx| {
+                let node = &self.graph[*idx];
+                (node.data.x, node.data.y, node.data.mass)
+            })
+            .collect();
+        let tree = QuadTree::new(&bodies);
+
+        for n1_idx in &self.node_indices {
+            // Anchors never move, forces on them would only pile up until they are released
+            let mut edges = self.graph.neighbors(*n1_idx).detach();
+            while let Some(n2_idx) = edges.next_node(&self.graph) {
+                let (n1, n2) = self.graph.index_twice_mut(*n1_idx, n2_idx);
+                if !n1.data.is_anchor {
+                    let f = attract_nodes(n1, n2, &self.parameters);
+                    n1.apply_force(f.0, f.1, dt, &self.parameters);
+                }
+            }
+
+            let n1 = &mut self.graph[*n1_idx];
+            if !n1.data.is_anchor {
+                let parameters = &self.parameters;
+                let (x, y, mass) = (n1.data.x, n1.data.y, n1.data.mass);
+                tree.repulsion(
+                    x,
+                    y,
+                    mass,
+                    parameters.theta,
+                    parameters.force_charge,
+                    |fx, fy| n1.apply_force(fx, fy, dt, parameters),
+                );
+                let f_center = attract_to_center(n1, center_x, center_y, parameters);
+                n1.apply_force(f_center.0, f_center.1, dt, parameters);
+                n1.update(dt, parameters);
+            }
+        }
+    }
+
+    /// Total kinetic energy of the nodes that are free to move.
+    ///
+    /// It goes towards zero as the layout settles.
+    pub fn kinetic_energy(&self) -> f32 {
+        self.graph
+            .node_weights()
+            .filter(|node| !node.data.is_anchor)
+            .map(|node| 0.5 * node.data.mass * (node.vx * node.vx + node.vy * node.vy))
+            .sum()
+    }
+
     /// Processes each node with a user-defined callback `cb`.
     pub fn visit_nodes<F: FnMut(&Node<UserNodeData>)>(&self, mut cb: F) {
         for n_idx in self.graph.node_indices() {
@@ -356,3 +460,49 @@ fn attract_to_center<D>(
     let strength = parameters.force_spring * distance * 0.5;
     (dx * strength, dy * strength)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{EdgeData, ForceGraph, NodeData, SimulationParameters};
+
+    const DT: f32 = 0.016;
+
+    // Pins a node for 600 steps, releases it and returns how far it moved in the next 10
+    fn release_after_pin(barnes_hut_threshold: usize) -> f32 {
+        let mut graph = <ForceGraph>::new(SimulationParameters {
+            barnes_hut_threshold,
+            ..Default::default()
+        });
+        let pinned = graph.add_node(NodeData {
+            x: 300.0,
+            ..Default::default()
+        });
+        let other = graph.add_node(NodeData::default());
+        graph.add_edge(pinned, other, EdgeData::default());
+
+        graph.set_anchor(pinned, true);
+        for _ in 0..600 {
+            graph.update(DT);
+        }
+        assert_eq!(graph.get_node_position(pinned), (300.0, 0.0));
+
+        graph.set_anchor(pinned, false);
+        for _ in 0..10 {
+            graph.update(DT);
+        }
+        let (x, y) = graph.get_node_position(pinned);
+        ((x - 300.0) * (x - 300.0) + y * y).sqrt()
+    }
+
+    #[test]
+    fn released_anchor_is_not_flung() {
+        let moved = release_after_pin(100);
+        assert!(moved < 100.0, "moved by {} in 10 steps", moved);
+    }
+
+    #[test]
+    fn released_anchor_is_not_flung_with_barnes_hut() {
+        let moved = release_after_pin(0);
+        assert!(moved < 100.0, "moved by {} in 10 steps", moved);
+    }
+}
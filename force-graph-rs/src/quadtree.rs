@@ -0,0 +1,279 @@
+//! Quadtree used to approximate the repulsion between far away nodes (Barnes-Hut).
+
+// Bodies closer than what this depth can tell apart are merged in the same cell
+const MAX_DEPTH: u32 = 32;
+
+enum Content {
+    Empty,
+    // One body, or several merged at the maximum depth
+    Body,
+    // Index of the first of the four children, which are stored next to each other
+    Split(usize),
+}
+
+struct Cell {
+    // Centre and half size of the square covered by the cell
+    cx: f32,
+    cy: f32,
+    half: f32,
+    // Total mass and centre of mass of the bodies in the cell
+    mass: f32,
+    x: f32,
+    y: f32,
+    content: Content,
+}
+
+impl Cell {
+    fn new(cx: f32, cy: f32, half: f32) -> Self {
+        Cell {
+            cx,
+            cy,
+            half,
+            mass: 0.0,
+            x: 0.0,
+            y: 0.0,
+            content: Content::Empty,
+        }
+    }
+
+    fn add(&mut self, x: f32, y: f32, mass: f32) {
+        let total = self.mass + mass;
+        // The first body is copied as is, so that it is found at distance zero from itself
+        if self.mass == 0.0 {
+            self.x = x;
+            self.y = y;
+        } else if total > 0.0 {
+            self.x = (self.x * self.mass + x * mass) / total;
+            self.y = (self.y * self.mass + y * mass) / total;
+        }
+        self.mass = total;
+    }
+
+    fn quadrant(&self, x: f32, y: f32) -> usize {
+        usize::from(x >= self.cx) | usize::from(y >= self.cy) << 1
+    }
+
+    fn contains(&self, x: f32, y: f32) -> bool {
+        (x - self.cx).abs() <= self.half && (y - self.cy).abs() <= self.half
+    }
+}
+
+pub(crate) struct QuadTree {
+    cells: Vec<Cell>,
+}
+
+impl QuadTree {
+    /// Builds the tree from the position and mass of every body.
+    pub(crate) fn new(bodies: &[(f32, f32, f32)]) -> Self {
+        let (mut min_x, mut min_y) = (f32::MAX, f32::MAX);
+        let (mut max_x, mut max_y) = (f32::MIN, f32::MIN);
+        for (x, y, _) in bodies {
+            min_x = min_x.min(*x);
+            min_y = min_y.min(*y);
+            max_x = max_x.max(*x);
+            max_y = max_y.max(*y);
+        }
+        let half = ((max_x - min_x).max(max_y - min_y) / 2.0).max(1.0);
+        let root = Cell::new((min_x + max_x) / 2.0, (min_y + max_y) / 2.0, half);
+        let mut tree = QuadTree {
+            cells: Vec::with_capacity(bodies.len() * 2),
+        };
+        tree.cells.push(root);
+        for (x, y, mass) in bodies {
+            tree.insert(*x, *y, *mass);
+        }
+        tree
+    }
+
+    fn insert(&mut self, x: f32, y: f32, mass: f32) {
+        let mut cell = 0;
+        let mut depth = 0;
+        loop {
+            match self.cells[cell].content {
+                Content::Empty => {
+                    self.cells[cell].add(x, y, mass);
+                    self.cells[cell].content = Content::Body;
+                    return;
+                }
+                Content::Body if depth >= MAX_DEPTH => {
+                    self.cells[cell].add(x, y, mass);
+                    return;
+                }
+                Content::Body => {
+                    // Moves the body already here one level down, then inserts as in a split cell
+                    let first = self.cells.len();
+                    let parent = &self.cells[cell];
+                    let (body_x, body_y, body_mass) = (parent.x, parent.y, parent.mass);
+                    let body_quadrant = parent.quadrant(body_x, body_y);
+                    let half = parent.half / 2.0;
+                    let (cx, cy) = (parent.cx, parent.cy);
+                    for quadrant in 0..4 {
+                        let dx = if quadrant & 1 == 1 { half } else { -half };
+                        let dy = if quadrant & 2 == 2 { half } else { -half };
+                        self.cells.push(Cell::new(cx + dx, cy + dy, half));
+                    }
+                    let child = &mut self.cells[first + body_quadrant];
+                    child.add(body_x, body_y, body_mass);
+                    child.content = Content::Body;
+                    self.cells[cell].content = Content::Split(first);
+                }
+                Content::Split(first) => {
+                    self.cells[cell].add(x, y, mass);
+                    cell = first + self.cells[cell].quadrant(x, y);
+                    depth += 1;
+                }
+            }
+        }
+    }
+
+    /// Calls `apply` with the force that each body, or group of far away bodies, exerts on a
+    /// body of the given position and mass. A group is used when its size divided by its
+    /// distance is below `theta`.
+    pub(crate) fn repulsion<F: FnMut(f32, f32)>(
+        &self,
+        x: f32,
+        y: f32,
+        mass: f32,
+        theta: f32,
+        force_charge: f32,
+        mut apply: F,
+    ) {
+        self.visit(0, x, y, theta, &mut |cell_x, cell_y, cell_mass| {
+            let dx = cell_x - x;
+            let dy = cell_y - y;
+            let distance = (dx * dx + dy * dy).sqrt();
+            // The body itself, or bodies in the exact same place which don't push each other
+            if distance == 0.0 {
+                return;
+            }
+            let strength = -force_charge * ((mass * cell_mass) / (distance * distance));
+            apply(dx / distance * strength, dy / distance * strength);
+        });
+    }
+
+    // Calls `body` with the position and mass of every body or group of bodies to consider
+    fn visit<F: FnMut(f32, f32, f32)>(
+        &self,
+        index: usize,
+        x: f32,
+        y: f32,
+        theta: f32,
+        body: &mut F,
+    ) {
+        let cell = &self.cells[index];
+        match cell.content {
+            Content::Empty => {}
+            Content::Body => body(cell.x, cell.y, cell.mass),
+            Content::Split(first) => {
+                let dx = cell.x - x;
+                let dy = cell.y - y;
+                let distance = (dx * dx + dy * dy).sqrt();
+                // A cell containing the body is always opened, so the body doesn't repel itself
+                if cell.contains(x, y) || cell.half * 2.0 >= theta * distance {
+                    for child in first..first + 4 {
+                        self.visit(child, x, y, theta, body);
+                    }
+                } else {
+                    body(cell.x, cell.y, cell.mass);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::QuadTree;
+    use crate::{repel_nodes, DefaultNodeIdx, Node, NodeData, SimulationParameters};
+
+    // Bodies spread on a 1000x1000 square, deterministic between runs
+    fn bodies(count: usize) -> Vec<(f32, f32, f32)> {
+        let mut seed: u32 = 7;
+        let mut next = move || {
+            seed = seed.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+            (seed >> 8) as f32 / (1 << 24) as f32
+        };
+        (0..count)
+            .map(|_| (next() * 1000.0, next() * 1000.0, 1.0 + next() * 10.0))
+            .collect()
+    }
+
+    fn node(x: f32, y: f32, mass: f32) -> Node {
+        Node {
+            data: NodeData {
+                x,
+                y,
+                mass,
+                ..Default::default()
+            },
+            index: DefaultNodeIdx::new(0),
+            vx: 0.0,
+            vy: 0.0,
+            ax: 0.0,
+            ay: 0.0,
+        }
+    }
+
+    fn tree_force(tree: &QuadTree, body: (f32, f32, f32), theta: f32) -> (f32, f32) {
+        let parameters = SimulationParameters::default();
+        let mut force = (0.0, 0.0);
+        tree.repulsion(
+            body.0,
+            body.1,
+            body.2,
+            theta,
+            parameters.force_charge,
+            |fx, fy| {
+                force.0 += fx;
+                force.1 += fy;
+            },
+        );
+        force
+    }
+
+    #[test]
+    fn a_body_alone_gets_no_force() {
+        let tree = QuadTree::new(&[(12.5, -3.25, 7.0)]);
+        assert_eq!(tree_force(&tree, (12.5, -3.25, 7.0), 0.9), (0.0, 0.0));
+    }
+
+    #[test]
+    fn no_body_repels_itself() {
+        let bodies = bodies(200);
+        let tree = QuadTree::new(&bodies);
+        for body in &bodies {
+            let mut seen = 0;
+            tree.repulsion(body.0, body.1, body.2, 0.0, 1.0, |_, _| seen += 1);
+            assert_eq!(seen, bodies.len() - 1);
+        }
+    }
+
+    #[test]
+    fn matches_the_exact_repulsion_at_small_theta() {
+        let parameters = SimulationParameters::default();
+        let bodies = bodies(200);
+        let tree = QuadTree::new(&bodies);
+        for (i, body) in bodies.iter().enumerate() {
+            let n1 = node(body.0, body.1, body.2);
+            let mut exact = (0.0, 0.0);
+            for (j, other) in bodies.iter().enumerate() {
+                if i != j {
+                    let f = repel_nodes(&n1, &node(other.0, other.1, other.2), &parameters);
+                    exact.0 += f.0;
+                    exact.1 += f.1;
+                }
+            }
+            let approximate = tree_force(&tree, *body, 0.3);
+            let error =
+                ((approximate.0 - exact.0).powi(2) + (approximate.1 - exact.1).powi(2)).sqrt();
+            let magnitude = (exact.0 * exact.0 + exact.1 * exact.1).sqrt();
+            assert!(
+                error <= 0.05 * magnitude,
+                "body {}: {:?} instead of {:?}",
+                i,
+                approximate,
+                exact
+            );
+        }
+    }
+}
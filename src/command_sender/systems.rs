@@ -1,6 +1,6 @@
 use super::sender_trait::CommandSender;
 use crate::components::{
-    CrashMarker, Drone, Edge, Leaf, LeafType, Node, SelectedMarker, SelectionSpriteMarker, Text,
+    CrashMarker, Drone, Edge, Leaf, LeafType, Node, SelectionSpriteMarker, Text,
 };
 use crate::core::utils::is_connected;
 use crate::settings::ModeConfig;
@@ -9,25 +9,36 @@ use bevy_trait_query::One;
 use std::collections::{HashMap, HashSet};
 use wg_2024::{controller::DroneCommand, network::NodeId};
 
+// Every drone with a crash marker is crashed at once, the checks are done on the whole group
 pub fn crash(
     mut commands: Commands,
-    mut drone_to_crash_query: Query<
-        (Entity, &Drone, &mut Node),
-        (With<SelectedMarker>, With<CrashMarker>),
-    >,
+    mut drone_to_crash_query: Query<(Entity, &mut Drone, &Node), With<CrashMarker>>,
     mut nodes_query: Query<
         (&mut Node, Option<&Leaf>, One<&mut dyn CommandSender>),
-        Without<SelectedMarker>,
+        Without<CrashMarker>,
     >,
     mut selected_sprite_query: Query<&mut Visibility, (With<SelectionSpriteMarker>, Without<Node>)>,
     edge_query: Query<(Entity, &Edge)>,
     text_query: Query<(Entity, &Text)>,
     mode: Res<ModeConfig>,
 ) {
-    let Some((entity, drone_crashing, node_crashing)) = drone_to_crash_query.iter_mut().next()
-    else {
+    if drone_to_crash_query.is_empty() {
         return;
+    }
+    let crashing: HashSet<NodeId> = drone_to_crash_query
+        .iter()
+        .map(|(_, _, node)| node.id)
+        .collect();
+    let group: Vec<Entity> = drone_to_crash_query
+        .iter()
+        .map(|(entity, _, _)| entity)
+        .collect();
+    let abort = |commands: &mut Commands| {
+        for entity in &group {
+            commands.entity(*entity).remove::<CrashMarker>();
+        }
     };
+
     let mut topology: HashMap<NodeId, HashSet<NodeId>> = HashMap::new();
     for (node, leaf, _sender) in nodes_query.iter() {
         if let Some(leaf) = leaf {
@@ -35,31 +46,53 @@ pub fn crash(
                 topology.insert(node.id, node.neighbours.clone());
                 continue;
             }
-            for ngb_id in &node_crashing.neighbours {
-                if node.id == *ngb_id
-                    && leaf.leaf_type == LeafType::Server
-                    && node.neighbours.len() <= 2
-                {
-                    println!("Aborting crash: Server should always have at least 2 connections");
-                    commands.entity(entity).remove::<CrashMarker>();
-                    return;
-                }
+            let lost = node.neighbours.intersection(&crashing).count();
+            if lost > 0 && leaf.leaf_type == LeafType::Server && node.neighbours.len() - lost < 2 {
+                println!("Aborting crash: Server should always have at least 2 connections");
+                abort(&mut commands);
+                return;
             }
         } else {
             topology.insert(node.id, node.neighbours.clone());
         };
     }
-    topology.insert(node_crashing.id, node_crashing.neighbours.clone());
-    if !is_connected(topology, Some(node_crashing.id), None) {
-        println!("Aborting crash: Crashing this drone will disconnect the network...aborting");
-        commands.entity(entity).remove::<CrashMarker>();
+    for id in &crashing {
+        topology.remove(id);
+    }
+    for neighbours in topology.values_mut() {
+        neighbours.retain(|id| !crashing.contains(id));
+    }
+    if !is_connected(topology, None, None) {
+        println!("Aborting crash: Crashing these drones will disconnect the network...aborting");
+        abort(&mut commands);
         return;
     }
-    let res = drone_crashing
-        .command_channel
-        .send(DroneCommand::Crash)
-        .map_err(|err| err.to_string());
-    if res.is_ok() {
+
+    // The checks hold for the whole group only, so no drone is crashed unless all of them
+    // still run. Setting the PDR they already have tells whether their thread is alive
+    for (_, mut drone, node) in &mut drone_to_crash_query {
+        let pdr = drone.pdr;
+        if drone.set_packet_drop_rate(pdr).is_err() {
+            println!("Aborting crash: drone {} is not running", node.id);
+            abort(&mut commands);
+            return;
+        }
+    }
+    // Adjacent drones crashed together must drop their senders to each other as well,
+    // otherwise their channels never disconnect and they keep waiting for packets
+    for (_, mut drone, node) in &mut drone_to_crash_query {
+        for ngb in node.neighbours.intersection(&crashing) {
+            if drone.remove_sender(*ngb).is_err() {
+                println!("Error removing sender from drone {}", node.id);
+            }
+        }
+    }
+
+    for (entity, drone_crashing, node_crashing) in drone_to_crash_query.iter() {
+        // The thread stopped since the check, the drone is gone either way
+        if let Err(err) = drone_crashing.command_channel.send(DroneCommand::Crash) {
+            println!("Error crashing drone {}: {err}", node_crashing.id);
+        }
         //Sending remove sender command to neighbours
         for (mut node, _leaf, mut sender) in &mut nodes_query {
             if node.neighbours.contains(&node_crashing.id) {
@@ -73,12 +106,9 @@ pub fn crash(
         }
         //Despawning the drone and its related entities
         commands.entity(entity).despawn();
-        for mut visibility in &mut selected_sprite_query {
-            *visibility = Visibility::Hidden;
-        }
         for (entity, edge) in edge_query.iter() {
             if edge.start_node == node_crashing.id || edge.end_node == node_crashing.id {
-                commands.entity(entity).despawn();
+                commands.entity(entity).despawn_recursive();
             }
         }
         for (text_entity, text) in text_query.iter() {
@@ -86,6 +116,9 @@ pub fn crash(
                 commands.entity(text_entity).despawn();
             }
         }
+        println!("Crashed drone {} successfully", node_crashing.id);
+    }
+    for mut visibility in &mut selected_sprite_query {
+        *visibility = Visibility::Hidden;
     }
-    println!("Crashed successfully");
 }
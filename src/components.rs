@@ -1,3 +1,4 @@
+use crate::resources::{index_node, unindex_node};
 use bevy::prelude::*;
 use common_structs::leaf::LeafCommand;
 use crossbeam_channel::Sender;
@@ -18,11 +19,13 @@ pub struct DroneBundle {
 }
 
 #[derive(Clone, Component)]
+#[component(on_add = index_node, on_remove = unindex_node)]
 pub struct Node {
     pub id: NodeId,
     pub entity_id: Entity,
     pub neighbours: HashSet<NodeId>,
     pub packet_channel: Sender<Packet>,
+    pub name_impl: String,
 }
 
 #[derive(Component)]
@@ -31,6 +34,12 @@ pub struct Edge {
     pub end_node: NodeId,
 }
 
+// Half of an edge, carrying the traffic from start to end when forward
+#[derive(Component)]
+pub struct EdgeLane {
+    pub forward: bool,
+}
+
 #[derive(Component)]
 pub struct SelectedMarker;
 
@@ -40,6 +49,35 @@ pub struct CrashMarker;
 #[derive(Component)]
 pub struct SelectionSpriteMarker;
 
+// Tint applied to a node while it is part of the replayed flood
+#[derive(Component)]
+pub struct FloodHighlight(pub Color);
+
+// Marks a node involved in a violated invariant
+#[derive(Component)]
+pub struct InvariantHighlight;
+
+pub const INVARIANT_COLOR: Color = Color::srgb(1.0, 0.0, 1.0);
+
+// Marks the nodes and the edge of the hop taken by the last stepped packet
+#[derive(Component)]
+pub struct StepHighlight;
+
+pub const STEP_COLOR: Color = Color::srgb(0.0, 0.8, 1.0);
+
+// Marks a drone whose crash, or an edge whose removal, would disconnect the network
+#[derive(Component)]
+pub struct FragileHighlight;
+
+pub const FRAGILE_COLOR: Color = Color::srgb(1.0, 0.45, 0.0);
+
+// Marks the nodes and edges that don't match the search filters
+#[derive(Component)]
+pub struct Dimmed;
+
+pub const DIM_ALPHA: f32 = 0.15;
+pub const DIM_EDGE_COLOR: Color = Color::srgb(0.15, 0.15, 0.15);
+
 #[derive(Component)]
 pub struct Text {
     pub entity_id: Entity,
@@ -62,6 +100,15 @@ pub enum LeafType {
     Client,
     Server,
 }
+impl LeafType {
+    pub fn color(&self) -> Color {
+        match self {
+            LeafType::Client => Color::srgb(1.0, 0.6, 0.6),
+            LeafType::Server => Color::srgb(0.1, 0.1, 0.4),
+        }
+    }
+}
+
 impl Display for LeafType {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
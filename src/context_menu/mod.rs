@@ -0,0 +1,25 @@
+/// This module contains the context menu plugin.
+/// Right-clicking a drone, a leaf, an edge or the empty canvas opens a menu next to the cursor
+/// with the actions that make sense there, all of them going through the existing events.
+mod resources;
+mod systems;
+
+use crate::timeline::timeline_live;
+use bevy::prelude::*;
+use resources::ContextMenu;
+use systems::{context_menu, open_context_menu};
+
+pub struct ContextMenuPlugin;
+
+impl Plugin for ContextMenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(ContextMenu::default());
+        // The history view is read-only, the hidden live graph can't be acted on
+        app.add_systems(
+            Update,
+            (open_context_menu, context_menu)
+                .chain()
+                .run_if(timeline_live),
+        );
+    }
+}
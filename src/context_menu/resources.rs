@@ -0,0 +1,21 @@
+use bevy::prelude::*;
+use wg_2024::network::NodeId;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MenuTarget {
+    Node(Entity),
+    Edge(Entity),
+    Canvas,
+}
+
+#[derive(Resource, Default)]
+pub struct ContextMenu {
+    // None while the menu is closed
+    pub target: Option<MenuTarget>,
+    // Where the menu was opened, in viewport and in world coordinates
+    pub screen_pos: Vec2,
+    pub world_pos: Vec2,
+    // Values edited in the menu before applying them
+    pub pdr: f32,
+    pub ngbs: [NodeId; 2],
+}
@@ -0,0 +1,318 @@
+use super::resources::{ContextMenu, MenuTarget};
+use crate::components::{CrashMarker, Drone, Edge, Leaf, Node};
+use crate::editing::edge_at;
+use crate::event_listener::resources::Bytes;
+use crate::event_listener::DisplayedInfo;
+use crate::events::{
+    AddDroneEvent, AddEdgeEvent, FitViewEvent, ReplaceDroneEvent, RmvEdgeEvent, SelectNodeEvent,
+};
+use crate::heatmap::EdgeHeat;
+use crate::resources::NodeIndex;
+use bevy::picking::{focus::HoverMap, pointer::PointerId};
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+use bevy_egui::{egui, EguiContexts};
+use std::collections::HashMap;
+use wg_2024::network::NodeId;
+
+pub fn open_context_menu(
+    mut menu: ResMut<ContextMenu>,
+    mut contexts: EguiContexts,
+    buttons: Res<ButtonInput<MouseButton>>,
+    hover_map: Res<HoverMap>,
+    window: Single<&Window, With<PrimaryWindow>>,
+    camera: Single<(&Camera, &GlobalTransform)>,
+    nodes: Query<(&Node, &Transform, Option<&Drone>)>,
+    edges: Query<(Entity, &Edge)>,
+    index: Res<NodeIndex>,
+) {
+    if !buttons.just_pressed(MouseButton::Right) || contexts.ctx_mut().is_pointer_over_area() {
+        return;
+    }
+    let (camera, camera_transform) = *camera;
+    let Some(screen_pos) = window.cursor_position() else {
+        return;
+    };
+    let Ok(world_pos) = camera.viewport_to_world_2d(camera_transform, screen_pos) else {
+        return;
+    };
+    let hovered = hover_map
+        .get(&PointerId::Mouse)
+        .and_then(|hits| hits.keys().find(|entity| nodes.contains(**entity)).copied());
+    let target = if let Some(entity) = hovered {
+        MenuTarget::Node(entity)
+    } else {
+        let positions: HashMap<NodeId, Vec2> = nodes
+            .iter()
+            .map(|(node, transform, _)| (node.id, transform.translation.truncate()))
+            .collect();
+        edge_at(
+            camera,
+            camera_transform,
+            screen_pos,
+            &positions,
+            edges.iter(),
+        )
+        .map_or(MenuTarget::Canvas, MenuTarget::Edge)
+    };
+    let drone_pdr = |entity: Entity| {
+        nodes
+            .get(entity)
+            .ok()
+            .and_then(|(_, _, drone)| drone)
+            .map(|drone| drone.pdr)
+    };
+    // On an edge the highest PDR of its drone ends, which is the one the link drops with
+    let pdr = match target {
+        MenuTarget::Node(entity) => drone_pdr(entity),
+        MenuTarget::Edge(entity) => edges.get(entity).ok().and_then(|(_, edge)| {
+            [edge.start_node, edge.end_node]
+                .into_iter()
+                .filter_map(|id| index.entity(id).and_then(drone_pdr))
+                .reduce(f32::max)
+        }),
+        MenuTarget::Canvas => None,
+    }
+    .unwrap_or(0.0);
+    *menu = ContextMenu {
+        target: Some(target),
+        screen_pos,
+        world_pos,
+        pdr,
+        ngbs: menu.ngbs,
+    };
+}
+
+// Bytes of fragments sent from a node to one of its neighbours
+fn bytes_sent(info: &DisplayedInfo, from: NodeId, to: NodeId) -> u64 {
+    info.drone
+        .get(&from)
+        .map(|data| &data.neighbours)
+        .or_else(|| info.leaf.get(&from).map(|data| &data.neighbours))
+        .and_then(|neighbours| neighbours.get(&to))
+        .map_or(0, |bytes| bytes.0)
+}
+
+pub fn context_menu(
+    mut commands: Commands,
+    mut contexts: EguiContexts,
+    mut menu: ResMut<ContextMenu>,
+    keys: Res<ButtonInput<KeyCode>>,
+    buttons: Res<ButtonInput<MouseButton>>,
+    mut drones: Query<&mut Drone>,
+    nodes: Query<(&Node, Option<&Leaf>)>,
+    edges: Query<&Edge>,
+    index: Res<NodeIndex>,
+    info: Res<DisplayedInfo>,
+    heat: Res<EdgeHeat>,
+    mut ew_add_drone: EventWriter<AddDroneEvent>,
+    mut ew_replace_drone: EventWriter<ReplaceDroneEvent>,
+    mut ew_add_edge: EventWriter<AddEdgeEvent>,
+    mut ew_rmv_edge: EventWriter<RmvEdgeEvent>,
+    mut ew_select: EventWriter<SelectNodeEvent>,
+    mut ew_fit: EventWriter<FitViewEvent>,
+) {
+    let Some(target) = menu.target else {
+        return;
+    };
+    if keys.just_pressed(KeyCode::Escape) {
+        menu.target = None;
+        return;
+    }
+    let menu = &mut *menu;
+    let mut ids: Vec<NodeId> = nodes.iter().map(|(node, _)| node.id).collect();
+    ids.sort_unstable();
+    let mut close = false;
+
+    let response = egui::Area::new(egui::Id::new("context_menu"))
+        .fixed_pos(egui::pos2(menu.screen_pos.x, menu.screen_pos.y))
+        .order(egui::Order::Foreground)
+        .show(contexts.ctx_mut(), |ui| {
+            egui::Frame::menu(ui.style()).show(ui, |ui| match target {
+                MenuTarget::Node(entity) => {
+                    let Ok((node, leaf)) = nodes.get(entity) else {
+                        close = true;
+                        return;
+                    };
+                    if let Some(leaf) = leaf {
+                        ui.label(format!("{} {}", leaf.leaf_type, node.id));
+                        ui.separator();
+                        if ui.button("Show messages").clicked() {
+                            ew_select.send(SelectNodeEvent {
+                                entity,
+                                additive: false,
+                            });
+                            close = true;
+                        }
+                        ui.menu_button("Disconnect from...", |ui| {
+                            let mut neighbours: Vec<_> = node.neighbours.iter().collect();
+                            neighbours.sort_unstable();
+                            for ngb in neighbours {
+                                if ui.button(ngb.to_string()).clicked() {
+                                    ew_rmv_edge.send(RmvEdgeEvent {
+                                        start_node: node.id,
+                                        end_node: *ngb,
+                                    });
+                                    close = true;
+                                }
+                            }
+                        });
+                        return;
+                    }
+
+                    ui.label(format!("Drone {}", node.id));
+                    ui.separator();
+                    if ui.button("Show stats").clicked() {
+                        ew_select.send(SelectNodeEvent {
+                            entity,
+                            additive: false,
+                        });
+                        close = true;
+                    }
+                    ui.horizontal(|ui| {
+                        ui.label("PDR");
+                        ui.add(
+                            egui::DragValue::new(&mut menu.pdr)
+                                .range(0.0..=1.0)
+                                .speed(0.01),
+                        );
+                        if ui.button("Set").clicked() {
+                            if let Ok(mut drone) = drones.get_mut(entity) {
+                                if drone.set_packet_drop_rate(menu.pdr).is_ok() {
+                                    println!("New PDR: {}", menu.pdr);
+                                }
+                            }
+                            close = true;
+                        }
+                    });
+                    ui.menu_button("Connect to...", |ui| {
+                        egui::ScrollArea::vertical()
+                            .max_height(300.0)
+                            .show(ui, |ui| {
+                                for id in ids
+                                    .iter()
+                                    .filter(|id| **id != node.id && !node.neighbours.contains(id))
+                                {
+                                    if ui.button(id.to_string()).clicked() {
+                                        ew_add_edge.send(AddEdgeEvent {
+                                            start_node: node.id,
+                                            end_node: *id,
+                                        });
+                                        close = true;
+                                    }
+                                }
+                            });
+                    });
+                    if ui
+                        .button("Replace implementation")
+                        .on_hover_text(
+                            "Crash it and spawn a drone from the factories in its place, \
+                             with the same neighbours and PDR",
+                        )
+                        .clicked()
+                    {
+                        ew_replace_drone.send(ReplaceDroneEvent { node: node.id });
+                        close = true;
+                    }
+                    if ui
+                        .add(egui::Button::new("Crash").fill(egui::Color32::DARK_RED))
+                        .clicked()
+                    {
+                        println!("Trying to crash the drone (id): {:?}", node.id);
+                        commands.entity(entity).insert(CrashMarker);
+                        close = true;
+                    }
+                }
+                MenuTarget::Edge(entity) => {
+                    let Ok(edge) = edges.get(entity) else {
+                        close = true;
+                        return;
+                    };
+                    let (a, b) = (edge.start_node, edge.end_node);
+                    ui.label(format!("Edge {a} - {b}"));
+                    ui.separator();
+                    ui.menu_button("Show traffic", |ui| {
+                        for (from, to) in [(a, b), (b, a)] {
+                            ui.label(format!(
+                                "{from} -> {to}: {} sent, {}/s now",
+                                Bytes(bytes_sent(&info, from, to)),
+                                Bytes(heat.rate(from, to) as u64)
+                            ));
+                        }
+                    });
+                    // The drones drop packets on all their links, there is no per-link setting
+                    let ends: Vec<Entity> = [a, b]
+                        .into_iter()
+                        .filter_map(|id| index.entity(id))
+                        .filter(|entity| drones.contains(*entity))
+                        .collect();
+                    ui.add_enabled_ui(!ends.is_empty(), |ui| {
+                        ui.menu_button("Apply impairment", |ui| {
+                            ui.label("Sets the PDR of the drones at its ends");
+                            ui.horizontal(|ui| {
+                                ui.label("PDR");
+                                ui.add(
+                                    egui::DragValue::new(&mut menu.pdr)
+                                        .range(0.0..=1.0)
+                                        .speed(0.01),
+                                );
+                                if ui.button("Apply").clicked() {
+                                    for entity in &ends {
+                                        if let Ok(mut drone) = drones.get_mut(*entity) {
+                                            if drone.set_packet_drop_rate(menu.pdr).is_ok() {
+                                                println!("New PDR: {}", menu.pdr);
+                                            }
+                                        }
+                                    }
+                                    close = true;
+                                }
+                            });
+                        });
+                    });
+                    if ui.button("Remove").clicked() {
+                        ew_rmv_edge.send(RmvEdgeEvent {
+                            start_node: a,
+                            end_node: b,
+                        });
+                        close = true;
+                    }
+                }
+                MenuTarget::Canvas => {
+                    ui.menu_button("Spawn drone here", |ui| {
+                        ui.horizontal(|ui| {
+                            ui.label("PDR");
+                            ui.add(
+                                egui::DragValue::new(&mut menu.pdr)
+                                    .range(0.0..=1.0)
+                                    .speed(0.01),
+                            );
+                        });
+                        ui.horizontal(|ui| {
+                            ui.label("Connected with");
+                            ui.add(egui::DragValue::new(&mut menu.ngbs[0]));
+                            ui.label("&");
+                            ui.add(egui::DragValue::new(&mut menu.ngbs[1]));
+                        });
+                        if ui.button("Spawn").clicked() {
+                            ew_add_drone.send(AddDroneEvent {
+                                pdr: menu.pdr,
+                                ngbs: menu.ngbs.to_vec(),
+                                position: Some(menu.world_pos),
+                            });
+                            close = true;
+                        }
+                    });
+                    if ui.button("Fit view").clicked() {
+                        ew_fit.send(FitViewEvent);
+                        close = true;
+                    }
+                }
+            });
+        })
+        .response;
+
+    // The right click that opened the menu must not close it
+    if close || (response.clicked_elsewhere() && !buttons.just_pressed(MouseButton::Right)) {
+        menu.target = None;
+    }
+}
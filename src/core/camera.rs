@@ -1,4 +1,9 @@
-use bevy::{input::mouse::AccumulatedMouseScroll, prelude::*, render::camera::ScalingMode};
+use crate::components::Node;
+use crate::events::FitViewEvent;
+use bevy::{
+    input::mouse::AccumulatedMouseScroll, prelude::*, render::camera::ScalingMode,
+    window::PrimaryWindow,
+};
 use std::ops::Range;
 
 #[derive(Debug, Resource)]
@@ -26,6 +31,8 @@ impl Plugin for CameraPlugin {
         });
         app.add_systems(Update, zoom);
         app.add_systems(Update, move_camera);
+        app.add_event::<FitViewEvent>();
+        app.add_systems(Update, fit_view);
     }
 }
 
@@ -59,6 +66,41 @@ fn zoom(
     }
 }
 
+// Centres the camera on the nodes and zooms so that all of them are visible
+fn fit_view(
+    mut reader: EventReader<FitViewEvent>,
+    camera: Single<(&mut Transform, &mut Projection), With<Camera>>,
+    nodes: Query<&Transform, (With<Node>, Without<Camera>)>,
+    window: Single<&Window, With<PrimaryWindow>>,
+    camera_settings: Res<CameraSettings>,
+) {
+    const MARGIN: f32 = 1.2;
+    if reader.read().count() == 0 {
+        return;
+    }
+    let mut points = nodes
+        .iter()
+        .map(|transform| transform.translation.truncate());
+    let Some(first) = points.next() else {
+        return;
+    };
+    let bounds = points.fold(
+        Rect::from_center_size(first, Vec2::ZERO),
+        |bounds, point| bounds.union_point(point),
+    );
+    let (mut transform, mut projection) = camera.into_inner();
+    transform.translation.x = bounds.center().x;
+    transform.translation.y = bounds.center().y;
+    if let Projection::Orthographic(ref mut orthographic) = *projection {
+        let aspect = window.width() / window.height().max(1.0);
+        let height = bounds.height().max(bounds.width() / aspect) * MARGIN;
+        orthographic.scale = (height / camera_settings.orthographic_viewport_height).clamp(
+            camera_settings.orthographic_zoom_range.start,
+            camera_settings.orthographic_zoom_range.end,
+        );
+    }
+}
+
 fn move_camera(
     keyboard_input: Res<ButtonInput<KeyCode>>,
     mut query: Query<&mut Transform, With<Camera>>,
@@ -1,10 +1,14 @@
-use crate::components::{Drone, DroneBundle, Edge, Leaf, LeafBundle, LeafType, Node, Text};
+use crate::components::{
+    Drone, DroneBundle, Edge, EdgeLane, Leaf, LeafBundle, LeafType, Node, Text,
+};
 use crate::window::{observer_drone, observer_leaf};
+use bevy::picking::PickingBehavior;
 use bevy::prelude::*;
 use network_initializer::network::{DroneInfo, LeafInfo, NodeInfo};
 use wg_2024::network::NodeId;
 
 const TEXT_SCALE: Vec3 = Vec3::new(0.8, 0.8, 0.8);
+pub const EDGE_COLOR: Color = Color::srgb(100.0, 100.0, 100.0);
 
 pub fn spawn_drone(
     commands: &mut Commands,
@@ -22,6 +26,7 @@ pub fn spawn_drone(
                     neighbours: node_info.neighbours.clone(),
                     packet_channel: node_info.packet_in_channel.clone(),
                     entity_id: Entity::PLACEHOLDER,
+                    name_impl: node_info.name_impl.clone(),
                 },
                 drone: Drone {
                     pdr: drone_info.pdr,
@@ -41,6 +46,7 @@ pub fn spawn_drone(
         neighbours: node_info.neighbours.clone(),
         packet_channel: node_info.packet_in_channel.clone(),
         entity_id,
+        name_impl: node_info.name_impl.clone(),
     });
     commands.entity(entity_id).observe(observer_drone);
     commands.spawn((
@@ -65,16 +71,14 @@ pub fn spawn_leaf(
 ) {
     let path: &str;
     let leaf_type: LeafType;
-    let color: Color;
     if client {
         path = "client.png";
         leaf_type = LeafType::Client;
-        color = Color::srgb(1.0, 0.6, 0.6);
     } else {
         path = "server.png";
         leaf_type = LeafType::Server;
-        color = Color::srgb(0.1, 0.1, 0.4);
     }
+    let color = leaf_type.color();
     let entity_id = commands
         .spawn((
             LeafBundle {
@@ -83,6 +87,7 @@ pub fn spawn_leaf(
                     neighbours: node_info.neighbours.clone(),
                     packet_channel: node_info.packet_in_channel.clone(),
                     entity_id: Entity::PLACEHOLDER,
+                    name_impl: node_info.name_impl.clone(),
                 },
                 leaf: Leaf {
                     command_channel: leaf_info.command_send_channel.clone(),
@@ -106,6 +111,7 @@ pub fn spawn_leaf(
         neighbours: node_info.neighbours.clone(),
         packet_channel: node_info.packet_in_channel.clone(),
         entity_id,
+        name_impl: node_info.name_impl.clone(),
     });
     commands.entity(entity_id).observe(observer_leaf);
     commands.spawn((
@@ -119,20 +125,36 @@ pub fn spawn_leaf(
     ));
 }
 
-pub fn spawn_edge(
-    commands: &mut Commands,
-    start_node: NodeId,
-    end_node: NodeId,
-    meshes: &mut ResMut<Assets<Mesh>>,
-    materials: &mut ResMut<Assets<ColorMaterial>>,
-) {
-    commands.spawn((
-        Edge {
-            start_node,
-            end_node,
-        },
-        Transform::default(),
-        Mesh2d(meshes.add(Rectangle::new(1.0, 1.0))),
-        MeshMaterial2d(materials.add(Color::srgb(100.0, 100.0, 100.0))),
-    ));
+// The lanes are plain sprites: they all share the default white image and are drawn in
+// batches, each one with its own colour, so no asset is created or freed per edge
+pub fn spawn_edge(commands: &mut Commands, start_node: NodeId, end_node: NodeId) {
+    commands
+        .spawn((
+            Edge {
+                start_node,
+                end_node,
+            },
+            Transform::default(),
+            Visibility::default(),
+        ))
+        .with_children(|parent| {
+            for forward in [true, false] {
+                let offset = if forward { 0.25 } else { -0.25 };
+                parent.spawn((
+                    EdgeLane { forward },
+                    // Below the nodes, edges are picked by their geometry in editing mode
+                    Transform {
+                        translation: Vec3::new(0.0, offset, -1.0),
+                        scale: Vec3::new(1.0, 0.5, 1.0),
+                        ..Default::default()
+                    },
+                    Sprite {
+                        color: EDGE_COLOR,
+                        custom_size: Some(Vec2::ONE),
+                        ..Default::default()
+                    },
+                    PickingBehavior::IGNORE,
+                ));
+            }
+        });
 }
@@ -1,5 +1,5 @@
-use super::creator::{spawn_drone, spawn_leaf};
-use crate::components::{Edge, SelectionSpriteMarker};
+use super::creator::{spawn_drone, spawn_edge, spawn_leaf};
+use crate::components::SelectionSpriteMarker;
 use crate::resources::{DroneListener, LeafListener, NetworkResource, Senders};
 use bevy::prelude::*;
 use network_initializer::network::TypeInfo;
@@ -19,8 +19,6 @@ impl Plugin for SpawnTopologyPlugin {
 fn initialize_sc(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<ColorMaterial>>,
     network: ResMut<NetworkResource>,
 ) {
     commands.insert_resource(DroneListener {
@@ -83,15 +81,7 @@ fn initialize_sc(
         }
         for neighbour_id in &node_info.neighbours {
             if !connection_set.contains(&(*node_id, *neighbour_id)) {
-                commands.spawn((
-                    Edge {
-                        start_node: *node_id,
-                        end_node: *neighbour_id,
-                    },
-                    Transform::default(),
-                    Mesh2d(meshes.add(Rectangle::new(1.0, 1.0))),
-                    MeshMaterial2d(materials.add(Color::srgb(100.0, 100.0, 100.0))),
-                ));
+                spawn_edge(&mut commands, *node_id, *neighbour_id);
                 connection_set.insert((*node_id, *neighbour_id));
                 connection_set.insert((*neighbour_id, *node_id));
             }
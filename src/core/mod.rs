@@ -13,6 +13,7 @@ use initializer::SpawnTopologyPlugin;
 pub mod sytems;
 use sytems::FunctionalityPlugins;
 
+use crate::resources::NodeIndex;
 use bevy::{
     prelude::*,
     winit::{UpdateMode, WinitSettings},
@@ -27,6 +28,7 @@ impl Plugin for CorePlugin {
         })
         .insert_resource(ClearColor(Color::srgb(0.4, 0.4, 0.8)))
         .insert_resource(Time::<Fixed>::from_seconds(1.0))
+        .insert_resource(NodeIndex::default())
         .add_plugins(CameraPlugin)
         .add_plugins(UtilsPlugin)
         .add_plugins(SpawnTopologyPlugin)
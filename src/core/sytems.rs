@@ -1,8 +1,8 @@
 use crate::command_sender::sender_trait::CommandSender;
-use crate::components::{Edge, Leaf, LeafType, Node};
+use crate::components::{CrashMarker, Drone, Edge, Leaf, LeafType, Node};
 use crate::core::{creator::spawn_drone, utils::is_connected};
-use crate::events::{AddDroneEvent, AddEdgeEvent, RmvEdgeEvent};
-use crate::resources::{NetworkResource, Senders};
+use crate::events::{AddDroneEvent, AddEdgeEvent, ReplaceDroneEvent, RmvEdgeEvent};
+use crate::resources::{NetworkResource, NodeIndex, Senders};
 use crate::settings::ModeConfig;
 use bevy::prelude::*;
 use bevy_trait_query::One;
@@ -19,9 +19,11 @@ pub struct FunctionalityPlugins;
 impl Plugin for FunctionalityPlugins {
     fn build(&self, app: &mut App) {
         app.add_event::<AddDroneEvent>();
+        app.add_event::<ReplaceDroneEvent>();
         app.add_event::<AddEdgeEvent>();
         app.add_event::<RmvEdgeEvent>();
         app.add_systems(Update, add_drone);
+        app.add_systems(Update, replace_drone);
         app.add_systems(Update, add_edge);
         app.add_systems(Update, remove_edge);
     }
@@ -31,47 +33,43 @@ pub fn add_drone(
     mut er_add_drone: EventReader<AddDroneEvent>,
     mut commands: Commands,
     asset_server: Res<AssetServer>,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<ColorMaterial>>,
     sender: Res<Senders>,
     mut nodes: Query<(&mut Node, Option<&Leaf>, One<&mut dyn CommandSender>)>,
     mode: Res<ModeConfig>,
     network: Res<NetworkResource>,
+    index: Res<NodeIndex>,
 ) {
     for add_node in er_add_drone.read() {
         let mut node_info: HashMap<NodeId, Sender<Packet>> = HashMap::new();
-        for (node, leaf, _sender) in nodes.iter() {
-            if mode.bypass_cheks {
+        if mode.bypass_cheks {
+            for (node, _leaf, _sender) in nodes.iter() {
                 node_info.insert(node.id, node.packet_channel.clone());
-                continue;
             }
-            for ngb_id in &add_node.ngbs {
-                if node.id == *ngb_id {
-                    if let Some(leaf) = leaf {
-                        if leaf.leaf_type == LeafType::Client && node.neighbours.len() > 1 {
-                            eprintln!("Client should be connected to at most 2 drones");
-                            return;
-                        }
-                    }
-                    node_info.insert(node.id, node.packet_channel.clone());
+        }
+        for ngb_id in &add_node.ngbs {
+            let Some((node, leaf, _sender)) = index
+                .entity(*ngb_id)
+                .and_then(|entity| nodes.get(entity).ok())
+            else {
+                continue;
+            };
+            if let Some(leaf) = leaf {
+                if !mode.bypass_cheks
+                    && leaf.leaf_type == LeafType::Client
+                    && node.neighbours.len() > 1
+                {
+                    eprintln!("Client should be connected to at most 2 drones");
+                    return;
                 }
             }
+            node_info.insert(node.id, node.packet_channel.clone());
         }
         if !(node_info.contains_key(&add_node.ngbs[0]) && node_info.contains_key(&add_node.ngbs[1]))
         {
             eprintln!("Nodes not present");
             return;
         }
-        let mut all_ids: Vec<NodeId> = nodes.iter().map(|(node, _, _)| node.id).collect();
-        all_ids.sort_unstable();
-        let mut node_id = 1;
-        for id in all_ids {
-            if id == node_id {
-                node_id += 1;
-            } else {
-                break;
-            }
-        }
+        let node_id = free_id(nodes.iter().map(|(node, _, _)| node.id));
 
         let node_info = create_drone(
             node_id,
@@ -87,123 +85,204 @@ pub fn add_drone(
                 node_id,
                 &node_info,
                 drone_info,
-                Vec3::new(-200.0, 0.0, 0.0),
+                add_node
+                    .position
+                    .map_or(Vec3::new(-200.0, 0.0, 0.0), |position| position.extend(0.0)),
             );
         } else {
             eprintln!("Wrong NI behaviour");
             return;
         }
-        for (mut node, _leaf, mut sender) in &mut nodes {
-            for ngb_id in &add_node.ngbs {
-                if node.id == *ngb_id {
-                    if sender
-                        .add_sender(node_id, node_info.packet_in_channel.clone())
-                        .is_ok()
-                    {
-                        node.neighbours.insert(node_id);
-                        spawn_edge(&mut commands, node_id, *ngb_id, &mut meshes, &mut materials);
-                    } else {
-                        eprintln!("Error adding sender");
-                        return;
-                    }
-                }
+        for ngb_id in &add_node.ngbs {
+            let Some(entity) = index.entity(*ngb_id) else {
+                continue;
+            };
+            let Ok((mut node, _leaf, mut sender)) = nodes.get_mut(entity) else {
+                continue;
+            };
+            if sender
+                .add_sender(node_id, node_info.packet_in_channel.clone())
+                .is_ok()
+            {
+                node.neighbours.insert(node_id);
+                spawn_edge(&mut commands, node_id, *ngb_id);
+            } else {
+                eprintln!("Error adding sender");
+                return;
             }
         }
         println!("Drone spawned successfully");
     }
 }
 
+// Smallest id not taken by any node
+fn free_id(ids: impl Iterator<Item = NodeId>) -> NodeId {
+    let mut all_ids: Vec<NodeId> = ids.collect();
+    all_ids.sort_unstable();
+    let mut node_id = 1;
+    for id in all_ids {
+        if id == node_id {
+            node_id += 1;
+        } else {
+            break;
+        }
+    }
+    node_id
+}
+
+// The new drone is linked to every neighbour of the old one before the old one is crashed,
+// so the crash checks see the network still connected through it
+pub fn replace_drone(
+    mut er_replace: EventReader<ReplaceDroneEvent>,
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    sender: Res<Senders>,
+    mut nodes: ParamSet<(
+        Query<(&Node, &Drone, &Transform)>,
+        Query<(&mut Node, One<&mut dyn CommandSender>)>,
+    )>,
+    network: Res<NetworkResource>,
+    index: Res<NodeIndex>,
+) {
+    // Drones spawned by this system are only queried from the next frame
+    let mut spawned: Vec<NodeId> = Vec::new();
+    for replace in er_replace.read() {
+        let Some(old_entity) = index.entity(replace.node) else {
+            eprintln!("Drone {} not present", replace.node);
+            continue;
+        };
+        let (pdr, position, neighbours) = {
+            let drones = nodes.p0();
+            let Ok((old, drone, transform)) = drones.get(old_entity) else {
+                eprintln!("Only drones can be replaced");
+                continue;
+            };
+            (drone.pdr, transform.translation, old.neighbours.clone())
+        };
+        let mut senders = nodes.p1();
+        let node_info: HashMap<NodeId, Sender<Packet>> = neighbours
+            .iter()
+            .filter_map(|id| {
+                index
+                    .entity(*id)
+                    .and_then(|entity| senders.get(entity).ok())
+            })
+            .map(|(node, _)| (node.id, node.packet_channel.clone()))
+            .collect();
+        let node_id = free_id(
+            senders
+                .iter()
+                .map(|(node, _)| node.id)
+                .chain(spawned.iter().copied()),
+        );
+        spawned.push(node_id);
+
+        let new_info = create_drone(
+            node_id,
+            pdr,
+            sender.drone_sender.clone(),
+            &node_info,
+            &network.data.drone_factories,
+        );
+        let TypeInfo::Drone(drone_info) = &new_info.type_info else {
+            eprintln!("Wrong NI behaviour");
+            continue;
+        };
+        spawn_drone(
+            &mut commands,
+            &asset_server,
+            node_id,
+            &new_info,
+            drone_info,
+            position,
+        );
+        for ngb_id in node_info.keys() {
+            let Some(entity) = index.entity(*ngb_id) else {
+                continue;
+            };
+            let Ok((mut node, mut sender)) = senders.get_mut(entity) else {
+                continue;
+            };
+            if sender
+                .add_sender(node_id, new_info.packet_in_channel.clone())
+                .is_ok()
+            {
+                node.neighbours.insert(node_id);
+                spawn_edge(&mut commands, node_id, *ngb_id);
+            } else {
+                eprintln!("Error adding sender for node {ngb_id}");
+            }
+        }
+        commands.entity(old_entity).insert(CrashMarker);
+        println!("Drone {} replaced by drone {node_id}", replace.node);
+    }
+}
+
 pub fn add_edge(
     mut er_add_edge: EventReader<AddEdgeEvent>,
     mut commands: Commands,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<ColorMaterial>>,
     mut nodes: Query<(&mut Node, Option<&Leaf>, One<&mut dyn CommandSender>)>,
     edges: Query<&Edge>,
     mode: Res<ModeConfig>,
+    index: Res<NodeIndex>,
 ) {
-    for edge in er_add_edge.read() {
+    // An invalid edge only skips its own event, the others are still handled
+    'events: for edge in er_add_edge.read() {
         if edge.start_node == edge.end_node {
             eprintln!("Can't connect a node to itself");
-            return;
+            continue;
         }
         if edges.iter().any(|e| {
             (e.start_node == edge.start_node && e.end_node == edge.end_node)
                 || (e.start_node == edge.end_node && e.end_node == edge.start_node)
         }) {
             eprintln!("Edge already exists");
-            return;
+            continue;
         }
         let mut node_info: HashMap<NodeId, Sender<Packet>> = HashMap::new();
-        for (node, leaf, _sender) in nodes.iter() {
-            if mode.bypass_cheks {
-                node_info.insert(node.id, node.packet_channel.clone());
+        for id in [edge.start_node, edge.end_node] {
+            let Some((node, leaf, _sender)) =
+                index.entity(id).and_then(|entity| nodes.get(entity).ok())
+            else {
                 continue;
-            }
-            if node.id == edge.start_node || node.id == edge.end_node {
-                node_info.insert(node.id, node.packet_channel.clone());
-                if let Some(leaf) = leaf {
-                    if leaf.leaf_type == LeafType::Client && node.neighbours.len() > 1 {
-                        eprintln!("Client should be connected to at most 2 drones");
-                        return;
-                    }
+            };
+            node_info.insert(node.id, node.packet_channel.clone());
+            if let Some(leaf) = leaf {
+                if !mode.bypass_cheks
+                    && leaf.leaf_type == LeafType::Client
+                    && node.neighbours.len() > 1
+                {
+                    eprintln!("Client should be connected to at most 2 drones");
+                    continue 'events;
                 }
             }
         }
         if !(node_info.contains_key(&edge.start_node) && node_info.contains_key(&edge.end_node)) {
             eprintln!("Can't connect nodes if either of them is not present");
-            return;
+            continue;
         }
-        let mut inserted = (false, false);
-
-        for (mut node, _leaf, mut sender) in &mut nodes {
-            if node.id == edge.start_node {
-                if sender
-                    .add_sender(
-                        edge.end_node,
-                        node_info.get(&edge.end_node).unwrap().clone(),
-                    )
-                    .is_ok()
-                {
-                    node.neighbours.insert(edge.end_node);
-                    inserted.0 = true;
-                } else {
-                    eprintln!("Error adding sender for node {}", edge.start_node);
-                    return;
-                }
-            }
-            if node.id == edge.end_node {
-                if sender
-                    .add_sender(
-                        edge.start_node,
-                        node_info.get(&edge.start_node).unwrap().clone(),
-                    )
-                    .is_ok()
-                {
-                    node.neighbours.insert(edge.start_node);
-                    inserted.1 = true;
-                } else {
-                    eprintln!("Error adding sender for node {}", edge.end_node);
-                    return;
-                }
-            }
-            if inserted.0 && inserted.1 {
-                break;
+        for (id, other) in [
+            (edge.start_node, edge.end_node),
+            (edge.end_node, edge.start_node),
+        ] {
+            let Some(entity) = index.entity(id) else {
+                eprintln!("Error processing one or both nodes.");
+                continue 'events;
+            };
+            let Ok((mut node, _leaf, mut sender)) = nodes.get_mut(entity) else {
+                eprintln!("Error processing one or both nodes.");
+                continue 'events;
+            };
+            if sender.add_sender(other, node_info[&other].clone()).is_ok() {
+                node.neighbours.insert(other);
+            } else {
+                eprintln!("Error adding sender for node {id}");
+                continue 'events;
             }
         }
 
-        if inserted.0 && inserted.1 {
-            spawn_edge(
-                &mut commands,
-                edge.start_node,
-                edge.end_node,
-                &mut meshes,
-                &mut materials,
-            );
-            println!("Edge added successfully");
-        } else {
-            eprintln!("Error processing one or both nodes.");
-        }
+        spawn_edge(&mut commands, edge.start_node, edge.end_node);
+        println!("Edge added successfully");
     }
 }
 
@@ -213,11 +292,12 @@ pub fn remove_edge(
     mut nodes: Query<(&mut Node, Option<&Leaf>, One<&mut dyn CommandSender>)>,
     edge_query: Query<(Entity, &Edge)>,
     mode: Res<ModeConfig>,
+    index: Res<NodeIndex>,
 ) {
-    for rmv_edge in er_add_edge.read() {
+    'events: for rmv_edge in er_add_edge.read() {
         if rmv_edge.start_node == rmv_edge.end_node {
             eprintln!("Can't remove self edge");
-            return;
+            continue;
         }
         let mut topology: HashMap<NodeId, HashSet<NodeId>> = HashMap::new();
         for (node, leaf, _sender) in nodes.iter() {
@@ -231,7 +311,7 @@ pub fn remove_edge(
                     && (node.id == rmv_edge.start_node || node.id == rmv_edge.end_node)
                 {
                     eprintln!("Server should always have at least 2 connections");
-                    return;
+                    continue 'events;
                 }
             }
             topology.insert(node.id, node.neighbours.clone());
@@ -240,7 +320,7 @@ pub fn remove_edge(
             && topology.contains_key(&rmv_edge.end_node))
         {
             eprintln!("Can't remove edge if either of the nodes is not present");
-            return;
+            continue;
         }
         if !is_connected(
             topology,
@@ -248,42 +328,32 @@ pub fn remove_edge(
             Some((rmv_edge.start_node, rmv_edge.end_node)),
         ) {
             eprintln!("Removing this edge will disconnect the network...aborting");
-            return;
+            continue;
         }
-        let mut removed = (false, false);
-        for (mut node, _leaf, mut sender) in &mut nodes {
-            if node.id == rmv_edge.start_node {
-                if sender.remove_sender(rmv_edge.end_node).is_ok() {
-                    node.neighbours.remove(&rmv_edge.end_node);
-                    removed.0 = true;
-                } else {
-                    eprintln!("Error removing sender for node {}", rmv_edge.start_node);
-                    return;
-                }
-            }
-            if node.id == rmv_edge.end_node {
-                if sender.remove_sender(rmv_edge.start_node).is_ok() {
-                    node.neighbours.remove(&rmv_edge.start_node);
-                    removed.1 = true;
-                } else {
-                    eprintln!("Error removing sender for node {}", rmv_edge.end_node);
-                    return;
-                }
-            }
-            if removed.0 && removed.1 {
-                break;
+        for (id, other) in [
+            (rmv_edge.start_node, rmv_edge.end_node),
+            (rmv_edge.end_node, rmv_edge.start_node),
+        ] {
+            let Some(entity) = index.entity(id) else {
+                continue 'events;
+            };
+            let Ok((mut node, _leaf, mut sender)) = nodes.get_mut(entity) else {
+                continue 'events;
+            };
+            if sender.remove_sender(other).is_ok() {
+                node.neighbours.remove(&other);
+            } else {
+                eprintln!("Error removing sender for node {id}");
+                continue 'events;
             }
         }
-        if removed.0 && removed.1 {
-            for (entity, edge) in edge_query.iter() {
-                if (edge.start_node == rmv_edge.start_node && edge.end_node == rmv_edge.end_node)
-                    || (edge.start_node == rmv_edge.end_node
-                        && edge.end_node == rmv_edge.start_node)
-                {
-                    commands.entity(entity).despawn();
-                    println!("Edge removed successfully");
-                    return;
-                }
+        for (entity, edge) in edge_query.iter() {
+            if (edge.start_node == rmv_edge.start_node && edge.end_node == rmv_edge.end_node)
+                || (edge.start_node == rmv_edge.end_node && edge.end_node == rmv_edge.start_node)
+            {
+                commands.entity(entity).despawn_recursive();
+                println!("Edge removed successfully");
+                break;
             }
         }
     }
@@ -1,4 +1,7 @@
-use crate::components::{Leaf, Node};
+use crate::components::{
+    Dimmed, FloodHighlight, FragileHighlight, InvariantHighlight, Leaf, Node, StepHighlight,
+    DIM_ALPHA, FRAGILE_COLOR, INVARIANT_COLOR, STEP_COLOR,
+};
 use bevy::prelude::*;
 use std::collections::{HashMap, HashSet};
 
@@ -6,14 +9,68 @@ pub struct UtilsPlugin;
 
 impl Plugin for UtilsPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Update, color);
+        app.add_systems(Update, (color, color_leaves));
     }
 }
 
-fn color(mut drone_query: Query<(&Node, &mut Sprite), Without<Leaf>>) {
-    for (node, mut sprite) in &mut drone_query {
-        let colors = int_to_rgb(node.packet_channel.len());
-        sprite.color = Color::srgb(colors.0, colors.1, colors.2);
+fn color(
+    mut drone_query: Query<
+        (
+            &Node,
+            &mut Sprite,
+            Option<&FloodHighlight>,
+            Has<InvariantHighlight>,
+            Has<StepHighlight>,
+            Has<FragileHighlight>,
+            Has<Dimmed>,
+        ),
+        Without<Leaf>,
+    >,
+) {
+    for (node, mut sprite, flood, invariant, step, fragile, dimmed) in &mut drone_query {
+        let color = if invariant {
+            INVARIANT_COLOR
+        } else if step {
+            STEP_COLOR
+        } else if let Some(flood) = flood {
+            flood.0
+        } else if fragile {
+            FRAGILE_COLOR
+        } else {
+            let colors = int_to_rgb(node.packet_channel.len());
+            Color::srgb(colors.0, colors.1, colors.2)
+        };
+        sprite.color = if dimmed {
+            color.with_alpha(DIM_ALPHA)
+        } else {
+            color
+        };
+    }
+}
+
+fn color_leaves(
+    mut leaf_query: Query<(
+        &Leaf,
+        &mut Sprite,
+        Option<&FloodHighlight>,
+        Has<InvariantHighlight>,
+        Has<StepHighlight>,
+        Has<Dimmed>,
+    )>,
+) {
+    for (leaf, mut sprite, flood, invariant, step, dimmed) in &mut leaf_query {
+        let color = if invariant {
+            INVARIANT_COLOR
+        } else if step {
+            STEP_COLOR
+        } else {
+            flood.map_or(leaf.leaf_type.color(), |flood| flood.0)
+        };
+        sprite.color = if dimmed {
+            color.with_alpha(DIM_ALPHA)
+        } else {
+            color
+        };
     }
 }
 
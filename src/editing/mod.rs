@@ -0,0 +1,26 @@
+/// This module contains the graph editing plugin.
+/// In editing mode dragging from a node to another connects them, with a rubber band following
+/// the cursor, and edges can be picked with a click and removed with the delete key.
+mod resources;
+mod systems;
+
+use crate::timeline::timeline_live;
+use bevy::prelude::*;
+pub use resources::EditMode;
+pub use systems::edge_at;
+use systems::{drop_link, edit_graph, editing_window, end_link, start_link};
+
+pub struct EditingPlugin;
+
+impl Plugin for EditingPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(EditMode::default());
+        app.add_systems(
+            Update,
+            (editing_window, edit_graph).chain().run_if(timeline_live),
+        );
+        app.add_observer(start_link);
+        app.add_observer(end_link);
+        app.add_observer(drop_link);
+    }
+}
@@ -0,0 +1,9 @@
+use bevy::prelude::*;
+
+#[derive(Resource, Default)]
+pub struct EditMode {
+    pub enabled: bool,
+    // Node the rubber band starts from while dragging
+    pub from: Option<Entity>,
+    pub selected_edge: Option<Entity>,
+}
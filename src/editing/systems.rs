@@ -0,0 +1,193 @@
+use super::resources::EditMode;
+use crate::components::{Edge, Node};
+use crate::events::{AddEdgeEvent, RmvEdgeEvent};
+use crate::timeline::Timeline;
+use bevy::picking::{focus::HoverMap, pointer::PointerId};
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+use bevy_egui::{egui, EguiContexts};
+use std::collections::HashMap;
+use wg_2024::network::NodeId;
+
+const LINK_COLOR: Color = Color::srgb(0.4, 1.0, 0.4);
+const SELECTED_EDGE_COLOR: Color = Color::srgb(1.0, 0.4, 0.2);
+// How far from an edge, in screen pixels, a click still picks it
+const PICK_PIXELS: f32 = 6.0;
+
+pub fn start_link(
+    trigger: Trigger<Pointer<DragStart>>,
+    mut edit: ResMut<EditMode>,
+    timeline: Res<Timeline>,
+    nodes: Query<(), With<Node>>,
+) {
+    // Observers have no run condition, the history view is checked here
+    if edit.enabled
+        && timeline.viewing.is_none()
+        && trigger.event().button == PointerButton::Primary
+        && nodes.contains(trigger.entity())
+    {
+        edit.from = Some(trigger.entity());
+    }
+}
+
+pub fn end_link(trigger: Trigger<Pointer<DragEnd>>, mut edit: ResMut<EditMode>) {
+    if edit.from == Some(trigger.entity()) {
+        edit.from = None;
+    }
+}
+
+// Fired on the node under the pointer when the drag is released
+pub fn drop_link(
+    trigger: Trigger<Pointer<DragDrop>>,
+    edit: Res<EditMode>,
+    timeline: Res<Timeline>,
+    nodes: Query<&Node>,
+    mut ew_add_edge: EventWriter<AddEdgeEvent>,
+) {
+    if !edit.enabled || timeline.viewing.is_some() {
+        return;
+    }
+    let (Ok(target), Ok(dropped)) = (
+        nodes.get(trigger.entity()),
+        nodes.get(trigger.event().dropped),
+    ) else {
+        return;
+    };
+    if target.id != dropped.id {
+        ew_add_edge.send(AddEdgeEvent {
+            start_node: dropped.id,
+            end_node: target.id,
+        });
+    }
+}
+
+/// Returns the edge under `screen_pos`, given in viewport coordinates, if there is one close enough.
+pub fn edge_at<'a>(
+    camera: &Camera,
+    camera_transform: &GlobalTransform,
+    screen_pos: Vec2,
+    positions: &HashMap<NodeId, Vec2>,
+    edges: impl Iterator<Item = (Entity, &'a Edge)>,
+) -> Option<Entity> {
+    let position = camera
+        .viewport_to_world_2d(camera_transform, screen_pos)
+        .ok()?;
+    let tolerance = camera
+        .viewport_to_world_2d(camera_transform, screen_pos + Vec2::X * PICK_PIXELS)
+        .map_or(PICK_PIXELS, |pos| pos.distance(position));
+    edges
+        .filter_map(|(entity, edge)| {
+            let start = *positions.get(&edge.start_node)?;
+            let end = *positions.get(&edge.end_node)?;
+            let segment = end - start;
+            let t = ((position - start).dot(segment) / segment.length_squared().max(f32::EPSILON))
+                .clamp(0.0, 1.0);
+            let distance = position.distance(start + segment * t);
+            (distance <= tolerance).then_some((entity, distance))
+        })
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(entity, _)| entity)
+}
+
+pub fn edit_graph(
+    mut edit: ResMut<EditMode>,
+    mut contexts: EguiContexts,
+    buttons: Res<ButtonInput<MouseButton>>,
+    keys: Res<ButtonInput<KeyCode>>,
+    hover_map: Res<HoverMap>,
+    window: Single<&Window, With<PrimaryWindow>>,
+    camera: Single<(&Camera, &GlobalTransform)>,
+    nodes: Query<(Entity, &Node, &Transform)>,
+    edges: Query<(Entity, &Edge)>,
+    mut ew_rmv_edge: EventWriter<RmvEdgeEvent>,
+    mut gizmos: Gizmos,
+) {
+    if !edit.enabled {
+        if edit.from.is_some() || edit.selected_edge.is_some() {
+            edit.from = None;
+            edit.selected_edge = None;
+        }
+        return;
+    }
+    let (camera, camera_transform) = *camera;
+    let positions: HashMap<NodeId, Vec2> = nodes
+        .iter()
+        .map(|(_, node, transform)| (node.id, transform.translation.truncate()))
+        .collect();
+    let screen_cursor = window.cursor_position();
+    let cursor =
+        screen_cursor.and_then(|pos| camera.viewport_to_world_2d(camera_transform, pos).ok());
+
+    if let (Some(from), Some(cursor)) = (edit.from, cursor) {
+        if let Ok((_, _, transform)) = nodes.get(from) {
+            gizmos.line_2d(transform.translation.truncate(), cursor, LINK_COLOR);
+        }
+    }
+
+    if let Some(screen_cursor) = screen_cursor {
+        let over_ui = contexts.ctx_mut().is_pointer_over_area();
+        let over_node = hover_map
+            .get(&PointerId::Mouse)
+            .is_some_and(|hits| hits.keys().any(|entity| nodes.contains(*entity)));
+        if buttons.just_pressed(MouseButton::Left) && !over_ui && !over_node {
+            edit.selected_edge = edge_at(
+                camera,
+                camera_transform,
+                screen_cursor,
+                &positions,
+                edges.iter(),
+            );
+        }
+    }
+
+    let Some(selected) = edit.selected_edge else {
+        return;
+    };
+    let Ok((_, edge)) = edges.get(selected) else {
+        edit.selected_edge = None;
+        return;
+    };
+    if let (Some(start), Some(end)) = (
+        positions.get(&edge.start_node),
+        positions.get(&edge.end_node),
+    ) {
+        gizmos.line_2d(*start, *end, SELECTED_EDGE_COLOR);
+    }
+    let typing = contexts.ctx_mut().wants_keyboard_input();
+    if !typing && keys.any_just_pressed([KeyCode::Delete, KeyCode::Backspace]) {
+        ew_rmv_edge.send(RmvEdgeEvent {
+            start_node: edge.start_node,
+            end_node: edge.end_node,
+        });
+        edit.selected_edge = None;
+    }
+}
+
+pub fn editing_window(
+    mut contexts: EguiContexts,
+    mut edit: ResMut<EditMode>,
+    edges: Query<&Edge>,
+    mut ew_rmv_edge: EventWriter<RmvEdgeEvent>,
+) {
+    egui::Window::new("Editing")
+        .default_open(false)
+        .show(contexts.ctx_mut(), |ui| {
+            ui.checkbox(&mut edit.enabled, "Edit the graph");
+            ui.label("Drag from a node to another to connect them");
+            ui.label("Click an edge and press Delete to remove it");
+            let Some(edge) = edit.selected_edge.and_then(|entity| edges.get(entity).ok()) else {
+                return;
+            };
+            ui.separator();
+            ui.horizontal(|ui| {
+                ui.label(format!("Edge {} - {}", edge.start_node, edge.end_node));
+                if ui.button("Remove").clicked() {
+                    ew_rmv_edge.send(RmvEdgeEvent {
+                        start_node: edge.start_node,
+                        end_node: edge.end_node,
+                    });
+                    edit.selected_edge = None;
+                }
+            });
+        });
+}
@@ -1,18 +1,24 @@
 /// This module contains the event listener system.
 /// The event listener system is responsible for listening events sent by nodes and storing them.
+/// Processing can be paused, leaving the events in their channels, and stepped one event at a time.
 pub mod resources;
 mod systems;
 
 use bevy::prelude::*;
-pub use resources::DisplayedInfo;
-use systems::{initialize_info, listen_drones_events, listen_leaves_events};
+pub use resources::{DisplayedInfo, Ingestion};
+pub use systems::ingestion_running;
+use systems::{
+    events_window, highlight_step, initialize_info, listen_drones_events, listen_leaves_events,
+};
 
 pub struct ListenerPlugin;
 
 impl Plugin for ListenerPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Startup, initialize_info)
+        app.insert_resource(Ingestion::default())
+            .add_systems(Startup, initialize_info)
             .add_systems(Update, listen_drones_events)
-            .add_systems(Update, listen_leaves_events);
+            .add_systems(Update, listen_leaves_events.after(listen_drones_events))
+            .add_systems(Update, (events_window, highlight_step).chain());
     }
 }
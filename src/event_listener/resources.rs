@@ -4,12 +4,14 @@ use std::ops::{AddAssign, Div};
 
 use common_structs::message::Message;
 use common_structs::types::Session;
+use crossbeam_channel::Receiver;
 use std::collections::HashMap;
 use wg_2024::network::NodeId;
+use wg_2024::packet::{NackType, Packet, PacketType};
 
 type Packets = u64;
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, Default, PartialEq)]
 pub struct Bytes(pub u64);
 
 impl fmt::Display for Bytes {
@@ -42,7 +44,43 @@ impl AddAssign<u64> for Bytes {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
+pub struct PacketTypeCounters {
+    pub fragments: Packets,
+    pub acks: Packets,
+    pub nacks_error_in_routing: Packets,
+    pub nacks_destination_is_drone: Packets,
+    pub nacks_dropped: Packets,
+    pub nacks_unexpected_recipient: Packets,
+    pub flood_requests: Packets,
+    pub flood_responses: Packets,
+}
+
+impl PacketTypeCounters {
+    pub fn count(&mut self, pack_type: &PacketType) {
+        match pack_type {
+            PacketType::MsgFragment(_) => self.fragments += 1,
+            PacketType::Ack(_) => self.acks += 1,
+            PacketType::Nack(nack) => match nack.nack_type {
+                NackType::ErrorInRouting(_) => self.nacks_error_in_routing += 1,
+                NackType::DestinationIsDrone => self.nacks_destination_is_drone += 1,
+                NackType::Dropped => self.nacks_dropped += 1,
+                NackType::UnexpectedRecipient(_) => self.nacks_unexpected_recipient += 1,
+            },
+            PacketType::FloodRequest(_) => self.flood_requests += 1,
+            PacketType::FloodResponse(_) => self.flood_responses += 1,
+        }
+    }
+
+    pub fn nacks(&self) -> Packets {
+        self.nacks_error_in_routing
+            + self.nacks_destination_is_drone
+            + self.nacks_dropped
+            + self.nacks_unexpected_recipient
+    }
+}
+
+#[derive(Debug, Clone, Default)]
 pub struct DroneData {
     // Number of packets sent and shortcutted are disjoint
     pub packets_sent: Packets,
@@ -50,8 +88,11 @@ pub struct DroneData {
     // In bytes
     pub data_sent: Bytes,
     pub data_dropped: Bytes,
+    pub fragments_dropped: Packets,
     // Value is the n of packets & data sent to each neighbour
     pub neighbours: HashMap<NodeId, Bytes>,
+    // Every packet sent, flood requests included, split by type
+    pub packet_types: PacketTypeCounters,
 }
 
 impl DroneData {
@@ -79,15 +120,62 @@ impl DroneData {
 }
 
 #[allow(unused)]
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct LeavesData {
     pub packets_sent: Packets,
     // In bytes
     pub data_sent: Bytes,
     // Number of requests / responses
     pub msg_n: u64,
+    // Value is the data sent to each neighbour
+    pub neighbours: HashMap<NodeId, Bytes>,
     // Messages
     pub messages: HashMap<Session, (Message, NodeId, bool)>,
+    // Time in seconds at which each message started to be sent
+    pub sessions_started: HashMap<Session, f32>,
+    // Every packet sent, flood requests included, split by type
+    pub packet_types: PacketTypeCounters,
+}
+
+#[derive(Debug, Clone)]
+pub struct SteppedEvent {
+    pub description: String,
+    // Hop taken by the packet, when it is known
+    pub hop: Option<(NodeId, NodeId)>,
+}
+
+// While paused the listeners leave the events in their channels, one at a time can be stepped
+#[derive(Debug, Default, Resource)]
+pub struct Ingestion {
+    pub paused: bool,
+    pub step: bool,
+    pub last_step: Option<SteppedEvent>,
+}
+
+impl Ingestion {
+    /// Takes the next event from the channel, if the controller is running or stepping.
+    pub fn next<T>(&mut self, receiver: &Receiver<T>) -> Option<T> {
+        if !self.paused {
+            return receiver.try_recv().ok();
+        }
+        if !self.step {
+            return None;
+        }
+        let event = receiver.try_recv().ok()?;
+        self.step = false;
+        Some(event)
+    }
+
+    /// Remembers the event just stepped, so that it can be shown.
+    pub fn stepped(&mut self, description: String, packet: Option<&Packet>) {
+        let hop = packet.and_then(|p| {
+            let header = &p.routing_header;
+            let from = header.hops.get(header.hop_index.checked_sub(1)?)?;
+            let to = header.hops.get(header.hop_index)?;
+            Some((*from, *to))
+        });
+        self.last_step = Some(SteppedEvent { description, hop });
+    }
 }
 
 #[derive(Debug, Resource)]
@@ -95,3 +183,13 @@ pub struct DisplayedInfo {
     pub drone: HashMap<NodeId, DroneData>,
     pub leaf: HashMap<NodeId, LeavesData>,
 }
+
+pub fn packet_type_name(packet: &Packet) -> String {
+    match &packet.pack_type {
+        PacketType::MsgFragment(_) => "Fragment".to_string(),
+        PacketType::Ack(_) => "Ack".to_string(),
+        PacketType::Nack(nack) => format!("Nack {:?}", nack.nack_type),
+        PacketType::FloodRequest(_) => "FloodRequest".to_string(),
+        PacketType::FloodResponse(_) => "FloodResponse".to_string(),
+    }
+}
@@ -1,14 +1,19 @@
 use crate::{
-    components::Node,
+    components::{Edge, Leaf, Node, StepHighlight},
+    flood::FloodTracker,
+    protocol::{resources::NodeView, ProtocolMonitor},
     resources::{DroneListener, LeafListener},
+    shortcut::Shortcuts,
 };
 use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
 
-use super::resources::{Bytes, DisplayedInfo, DroneData, LeavesData};
+use super::resources::{packet_type_name, Bytes, DisplayedInfo, Ingestion};
 use common_structs::leaf::LeafEvent;
 use std::collections::HashMap;
 use wg_2024::{
     controller::DroneEvent,
+    network::NodeId,
     packet::{Packet, PacketType},
 };
 
@@ -21,49 +26,68 @@ pub fn initialize_info(mut commands: Commands) {
 
 pub fn listen_drones_events(
     drone_listener: Res<DroneListener>,
-    node_query: Query<&Node>,
+    node_query: Query<(&Node, Has<Leaf>)>,
     mut info: ResMut<DisplayedInfo>,
+    mut floods: ResMut<FloodTracker>,
+    mut monitor: ResMut<ProtocolMonitor>,
+    mut shortcuts: ResMut<Shortcuts>,
+    mut ingestion: ResMut<Ingestion>,
+    time: Res<Time>,
 ) {
-    while let Ok(event) = drone_listener.receiver.try_recv() {
+    let topology = topology_view(&node_query);
+    while let Some(event) = ingestion.next(&drone_listener.receiver) {
+        if ingestion.paused {
+            let (description, packet) = describe_drone_event(&event);
+            ingestion.stepped(description, packet);
+        }
+        match &event {
+            DroneEvent::PacketSent(p) => {
+                monitor.check_sent(p, true, &topology, time.elapsed_secs());
+            }
+            DroneEvent::PacketDropped(p) => monitor.check_dropped(p, time.elapsed_secs()),
+            DroneEvent::ControllerShortcut(_) => {}
+        }
         match event {
             DroneEvent::PacketDropped(p) => {
                 if p.routing_header.hop_index > 0
                     && p.routing_header.hop_index <= p.routing_header.hops.len()
                 {
                     let hop = p.routing_header.hops[p.routing_header.hop_index - 1];
-                    let entry = info.drone.entry(hop).or_insert(DroneData {
-                        packets_sent: 0,
-                        packets_shortcutted: 0,
-                        data_sent: Bytes(0),
-                        data_dropped: Bytes(0),
-                        neighbours: HashMap::default(),
-                    });
+                    let entry = info.drone.entry(hop).or_default();
 
                     if let PacketType::MsgFragment(fragment) = p.pack_type {
                         entry.data_dropped += u64::from(fragment.length);
+                        entry.fragments_dropped += 1;
                     }
                 } else {
                     eprintln!("Invalid routing header: {:?}", p.routing_header);
                 }
             }
             DroneEvent::PacketSent(p) => {
-                if let PacketType::FloodRequest(_) = p.pack_type {
+                // Flood requests have no routing header, the sender is the last one in the trace
+                if let PacketType::FloodRequest(flood) = &p.pack_type {
+                    if let Some((sender, _)) = flood.path_trace.last() {
+                        let entry = info.drone.entry(*sender).or_default();
+                        entry.packet_types.count(&p.pack_type);
+                    }
+                    floods.record_request(flood, time.elapsed_secs());
                     continue;
                 }
+                if let PacketType::FloodResponse(flood) = &p.pack_type {
+                    if let Some(sender) = p.routing_header.hops.first() {
+                        let generated = p.routing_header.hop_index == 1;
+                        floods.record_response(flood, *sender, generated, time.elapsed_secs());
+                    }
+                }
                 if p.routing_header.hop_index > 0
                     && p.routing_header.hop_index <= p.routing_header.hops.len()
                 {
                     let entry = info
                         .drone
                         .entry(p.routing_header.hops[p.routing_header.hop_index - 1])
-                        .or_insert(DroneData {
-                            packets_sent: 0,
-                            packets_shortcutted: 0,
-                            data_sent: Bytes(0),
-                            data_dropped: Bytes(0),
-                            neighbours: HashMap::default(),
-                        });
+                        .or_default();
                     entry.packets_sent += 1;
+                    entry.packet_types.count(&p.pack_type);
                     if let PacketType::MsgFragment(fragment) = p.pack_type {
                         entry.data_sent += u64::from(fragment.length);
                         entry
@@ -83,15 +107,9 @@ pub fn listen_drones_events(
                     let entry = info
                         .drone
                         .entry(p.routing_header.hops[p.routing_header.hop_index - 1])
-                        .or_insert(DroneData {
-                            packets_sent: 0,
-                            packets_shortcutted: 0,
-                            data_sent: Bytes(0),
-                            data_dropped: Bytes(0),
-                            neighbours: HashMap::default(),
-                        });
+                        .or_default();
                     entry.packets_shortcutted += 1;
-                    shortcut(&node_query, &p);
+                    shortcuts.submit(p, time.elapsed_secs());
                 } else {
                     eprintln!("Invalid routing header: {:?}", p.routing_header);
                 }
@@ -102,37 +120,61 @@ pub fn listen_drones_events(
 
 pub fn listen_leaves_events(
     leaf_listener: Res<LeafListener>,
-    node_query: Query<&Node>,
+    node_query: Query<(&Node, Has<Leaf>)>,
     mut info: ResMut<DisplayedInfo>,
+    mut floods: ResMut<FloodTracker>,
+    mut monitor: ResMut<ProtocolMonitor>,
+    mut shortcuts: ResMut<Shortcuts>,
+    mut ingestion: ResMut<Ingestion>,
+    time: Res<Time>,
 ) {
-    while let Ok(event) = leaf_listener.receiver.try_recv() {
+    let topology = topology_view(&node_query);
+    while let Some(event) = ingestion.next(&leaf_listener.receiver) {
+        if ingestion.paused {
+            let (description, packet) = describe_leaf_event(&event);
+            ingestion.stepped(description, packet);
+        }
+        if let LeafEvent::PacketSend(p) = &event {
+            monitor.check_sent(p, false, &topology, time.elapsed_secs());
+        }
         match event {
             LeafEvent::PacketSend(p) => {
-                if let PacketType::FloodRequest(_) = p.pack_type {
+                if let PacketType::FloodRequest(flood) = &p.pack_type {
+                    if let Some((sender, _)) = flood.path_trace.last() {
+                        let entry = info.leaf.entry(*sender).or_default();
+                        entry.packet_types.count(&p.pack_type);
+                    }
+                    floods.record_request(flood, time.elapsed_secs());
                     continue;
                 }
+                if let PacketType::FloodResponse(flood) = &p.pack_type {
+                    if let Some(sender) = p.routing_header.hops.first() {
+                        let generated = p.routing_header.hop_index == 1;
+                        floods.record_response(flood, *sender, generated, time.elapsed_secs());
+                    }
+                }
                 if p.routing_header.hop_index > 0
                     && p.routing_header.hop_index <= p.routing_header.hops.len()
                 {
                     let hop = p.routing_header.hops[p.routing_header.hop_index - 1];
-                    let entry = info.leaf.entry(hop).or_insert(LeavesData {
-                        packets_sent: 0,
-                        data_sent: Bytes(0),
-                        msg_n: 0,
-                        messages: HashMap::default(),
-                    });
+                    let entry = info.leaf.entry(hop).or_default();
 
                     entry.packets_sent += 1;
+                    entry.packet_types.count(&p.pack_type);
 
                     if let PacketType::MsgFragment(fragment) = p.pack_type {
                         entry.data_sent += u64::from(fragment.length);
+                        if let Some(next) = p.routing_header.hops.get(p.routing_header.hop_index) {
+                            entry.neighbours.entry(*next).or_default().0 +=
+                                u64::from(fragment.length);
+                        }
                     }
                 } else {
                     eprintln!("Invalid routing header: {:?}", p.routing_header);
                 }
             }
             LeafEvent::ControllerShortcut(p) => {
-                shortcut(&node_query, &p);
+                shortcuts.submit(p, time.elapsed_secs());
             }
             LeafEvent::MessageStartSend {
                 start,
@@ -140,21 +182,12 @@ pub fn listen_leaves_events(
                 dest,
                 message: m,
             } => {
-                let entry = info.leaf.entry(start).or_insert(LeavesData {
-                    packets_sent: 0,
-                    data_sent: Bytes(0),
-                    msg_n: 0,
-                    messages: HashMap::default(),
-                });
+                let entry = info.leaf.entry(start).or_default();
                 entry.messages.insert(session, (m, dest, false));
+                entry.sessions_started.insert(session, time.elapsed_secs());
             }
             LeafEvent::MessageFullySent(start, session) => {
-                let entry = info.leaf.entry(start).or_insert(LeavesData {
-                    packets_sent: 0,
-                    data_sent: Bytes(0),
-                    msg_n: 0,
-                    messages: HashMap::default(),
-                });
+                let entry = info.leaf.entry(start).or_default();
                 if let Some((_, _, ended)) = entry.messages.get_mut(&session) {
                     if !*ended {
                         *ended = true;
@@ -166,21 +199,140 @@ pub fn listen_leaves_events(
     }
 }
 
-fn shortcut(node_query: &Query<&Node>, packet: &Packet) {
-    let Some(dest) = &packet.routing_header.destination() else {
-        return eprintln!("### SHORTCUT: NO DESTINATION");
+fn describe_drone_event(event: &DroneEvent) -> (String, Option<&Packet>) {
+    let (action, p) = match event {
+        DroneEvent::PacketSent(p) => ("sent", p),
+        DroneEvent::PacketDropped(p) => ("dropped", p),
+        DroneEvent::ControllerShortcut(p) => ("shortcutted", p),
     };
+    (
+        format!(
+            "Drone {action} {} (session {})",
+            packet_type_name(p),
+            p.session_id
+        ),
+        Some(p),
+    )
+}
 
-    let Some(node) = node_query.iter().find(|&node| *dest == node.id) else {
-        return eprintln!("### SHORTCUT: DIDN'T FIND DESTINATION");
-    };
+fn describe_leaf_event(event: &LeafEvent) -> (String, Option<&Packet>) {
+    match event {
+        LeafEvent::PacketSend(p) => (
+            format!(
+                "Leaf sent {} (session {})",
+                packet_type_name(p),
+                p.session_id
+            ),
+            Some(p),
+        ),
+        LeafEvent::ControllerShortcut(p) => (
+            format!(
+                "Leaf shortcutted {} (session {})",
+                packet_type_name(p),
+                p.session_id
+            ),
+            Some(p),
+        ),
+        LeafEvent::MessageStartSend {
+            start,
+            session,
+            dest,
+            ..
+        } => (
+            format!("Leaf {start} started message {session} to {dest}"),
+            None,
+        ),
+        LeafEvent::MessageFullySent(start, session) => {
+            (format!("Leaf {start} fully sent message {session}"), None)
+        }
+    }
+}
+
+fn topology_view<'a>(node_query: &'a Query<(&Node, Has<Leaf>)>) -> HashMap<NodeId, NodeView<'a>> {
+    node_query
+        .iter()
+        .map(|(node, is_leaf)| {
+            (
+                node.id,
+                NodeView {
+                    neighbours: &node.neighbours,
+                    is_leaf,
+                },
+            )
+        })
+        .collect()
+}
+
+pub fn highlight_step(
+    mut commands: Commands,
+    ingestion: Res<Ingestion>,
+    nodes: Query<(Entity, &Node, Has<StepHighlight>)>,
+    edges: Query<(Entity, &Edge, Has<StepHighlight>)>,
+) {
+    let hop = ingestion
+        .last_step
+        .as_ref()
+        .and_then(|step| step.hop)
+        .filter(|_| ingestion.paused);
+    let on_hop = |a: NodeId, b: NodeId| hop.is_some_and(|(from, to)| (a, b) == (from, to));
 
-    if node.packet_channel.send(packet.clone()).is_ok() {
-        println!(
-            "### SHORTCUT: Node with ID: {}, has received the packet: {}",
-            node.id, packet
-        );
-    } else {
-        eprintln!("### SHORTCUT: failed to shortcut");
+    for (entity, node, highlighted) in nodes.iter() {
+        let wanted = hop.is_some_and(|(from, to)| node.id == from || node.id == to);
+        if wanted && !highlighted {
+            commands.entity(entity).insert(StepHighlight);
+        } else if !wanted && highlighted {
+            commands.entity(entity).remove::<StepHighlight>();
+        }
     }
+    for (entity, edge, highlighted) in edges.iter() {
+        let wanted =
+            on_hop(edge.start_node, edge.end_node) || on_hop(edge.end_node, edge.start_node);
+        if wanted && !highlighted {
+            commands.entity(entity).insert(StepHighlight);
+        } else if !wanted && highlighted {
+            commands.entity(entity).remove::<StepHighlight>();
+        }
+    }
+}
+
+pub fn events_window(
+    mut contexts: EguiContexts,
+    mut ingestion: ResMut<Ingestion>,
+    drone_listener: Res<DroneListener>,
+    leaf_listener: Res<LeafListener>,
+) {
+    egui::Window::new("Events")
+        .default_open(false)
+        .show(contexts.ctx_mut(), |ui| {
+            let backlog = drone_listener.receiver.len() + leaf_listener.receiver.len();
+            ui.horizontal(|ui| {
+                if ingestion.paused {
+                    if ui.button("Resume").clicked() {
+                        ingestion.paused = false;
+                        ingestion.last_step = None;
+                    }
+                    if ui
+                        .add_enabled(backlog > 0, egui::Button::new("Step"))
+                        .clicked()
+                    {
+                        ingestion.step = true;
+                    }
+                } else if ui.button("Pause").clicked() {
+                    ingestion.paused = true;
+                }
+            });
+            ui.label(format!("Backlog: {backlog} events"));
+            if let Some(step) = &ingestion.last_step {
+                ui.separator();
+                ui.label(&step.description);
+                if let Some((from, to)) = step.hop {
+                    ui.label(format!("Hop: {from} -> {to}"));
+                }
+            }
+        });
+}
+
+/// Run condition for the systems that stop while event processing is paused.
+pub fn ingestion_running(ingestion: Res<Ingestion>) -> bool {
+    !ingestion.paused
 }
@@ -5,6 +5,14 @@ use wg_2024::network::NodeId;
 pub struct AddDroneEvent {
     pub pdr: f32,
     pub ngbs: Vec<NodeId>,
+    // Where to place the new drone, in world coordinates
+    pub position: Option<Vec2>,
+}
+
+// Swaps a drone for a new one from the factories, linked to the same neighbours
+#[derive(Event)]
+pub struct ReplaceDroneEvent {
+    pub node: NodeId,
 }
 
 #[derive(Event)]
@@ -18,3 +26,13 @@ pub struct RmvEdgeEvent {
     pub start_node: NodeId,
     pub end_node: NodeId,
 }
+
+#[derive(Event)]
+pub struct FitViewEvent;
+
+#[derive(Event)]
+pub struct SelectNodeEvent {
+    pub entity: Entity,
+    // Toggle the node in the current selection instead of replacing it
+    pub additive: bool,
+}
@@ -0,0 +1,20 @@
+/// This module contains the export plugin.
+/// The export plugin writes the statistics collected by the event listener to disk,
+/// as CSV tables and as a single JSON document, either on demand or when the app closes.
+mod resources;
+mod systems;
+
+use bevy::prelude::*;
+pub use resources::ExportConfig;
+pub use systems::write_export;
+use systems::{export_on_exit, export_stats};
+
+pub struct ExportPlugin;
+
+impl Plugin for ExportPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(ExportConfig { on_exit: false });
+        app.add_systems(Update, export_stats);
+        app.add_systems(Last, export_on_exit);
+    }
+}
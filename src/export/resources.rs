@@ -0,0 +1,94 @@
+use crate::event_listener::resources::PacketTypeCounters;
+use bevy::prelude::*;
+use serde::Serialize;
+use wg_2024::network::NodeId;
+
+pub const EXPORT_DIR: &str = "stats_export";
+
+#[derive(Resource)]
+pub struct ExportConfig {
+    pub on_exit: bool,
+}
+
+#[derive(Serialize)]
+pub struct DroneRow {
+    pub node_id: NodeId,
+    pub packets_sent: u64,
+    pub packets_shortcutted: u64,
+    pub data_sent: u64,
+    pub data_dropped: u64,
+    pub fragments_dropped: u64,
+    // Pairs of neighbour id and usage percentage
+    pub neighbour_usage_percentages: Vec<(NodeId, u32)>,
+    pub packet_types: PacketTypeRow,
+}
+
+#[derive(Serialize)]
+pub struct LeafRow {
+    pub node_id: NodeId,
+    pub packets_sent: u64,
+    pub data_sent: u64,
+    pub msg_n: u64,
+    pub packet_types: PacketTypeRow,
+}
+
+#[derive(Serialize)]
+pub struct PacketTypeRow {
+    pub fragments: u64,
+    pub acks: u64,
+    pub nacks_error_in_routing: u64,
+    pub nacks_destination_is_drone: u64,
+    pub nacks_dropped: u64,
+    pub nacks_unexpected_recipient: u64,
+    pub flood_requests: u64,
+    pub flood_responses: u64,
+}
+
+impl PacketTypeRow {
+    pub const CSV_HEADER: &'static str = "fragments,acks,nacks_error_in_routing,nacks_destination_is_drone,nacks_dropped,nacks_unexpected_recipient,flood_requests,flood_responses";
+
+    pub fn to_csv(&self) -> String {
+        format!(
+            "{},{},{},{},{},{},{},{}",
+            self.fragments,
+            self.acks,
+            self.nacks_error_in_routing,
+            self.nacks_destination_is_drone,
+            self.nacks_dropped,
+            self.nacks_unexpected_recipient,
+            self.flood_requests,
+            self.flood_responses
+        )
+    }
+}
+
+impl From<&PacketTypeCounters> for PacketTypeRow {
+    fn from(counters: &PacketTypeCounters) -> Self {
+        PacketTypeRow {
+            fragments: counters.fragments,
+            acks: counters.acks,
+            nacks_error_in_routing: counters.nacks_error_in_routing,
+            nacks_destination_is_drone: counters.nacks_destination_is_drone,
+            nacks_dropped: counters.nacks_dropped,
+            nacks_unexpected_recipient: counters.nacks_unexpected_recipient,
+            flood_requests: counters.flood_requests,
+            flood_responses: counters.flood_responses,
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct SessionRow {
+    pub node_id: NodeId,
+    pub session: u64,
+    pub destination: NodeId,
+    pub completed: bool,
+    pub message: String,
+}
+
+#[derive(Serialize)]
+pub struct StatsExport {
+    pub drones: Vec<DroneRow>,
+    pub leaves: Vec<LeafRow>,
+    pub sessions: Vec<SessionRow>,
+}
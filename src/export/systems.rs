@@ -0,0 +1,164 @@
+use super::resources::{
+    DroneRow, ExportConfig, LeafRow, PacketTypeRow, SessionRow, StatsExport, EXPORT_DIR,
+};
+use crate::event_listener::DisplayedInfo;
+use crate::settings::ExportEvent;
+use bevy::prelude::*;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub fn export_stats(mut reader: EventReader<ExportEvent>, info: Res<DisplayedInfo>) {
+    for _ in reader.read() {
+        match write_export(&info) {
+            Ok(dir) => println!("Stats exported to {}", dir.display()),
+            Err(err) => eprintln!("Error exporting stats: {err}"),
+        }
+    }
+}
+
+pub fn export_on_exit(
+    mut reader: EventReader<AppExit>,
+    config: Res<ExportConfig>,
+    info: Res<DisplayedInfo>,
+) {
+    if reader.read().count() == 0 || !config.on_exit {
+        return;
+    }
+    match write_export(&info) {
+        Ok(dir) => println!("Stats exported to {}", dir.display()),
+        Err(err) => eprintln!("Error exporting stats: {err}"),
+    }
+}
+
+fn collect(info: &DisplayedInfo) -> StatsExport {
+    let mut drones: Vec<DroneRow> = info
+        .drone
+        .iter()
+        .map(|(id, data)| DroneRow {
+            node_id: *id,
+            packets_sent: data.packets_sent,
+            packets_shortcutted: data.packets_shortcutted,
+            data_sent: data.data_sent.0,
+            data_dropped: data.data_dropped.0,
+            fragments_dropped: data.fragments_dropped,
+            neighbour_usage_percentages: data.neighbour_usage_percentages(),
+            packet_types: PacketTypeRow::from(&data.packet_types),
+        })
+        .collect();
+    drones.sort_by_key(|row| row.node_id);
+
+    let mut leaves: Vec<LeafRow> = info
+        .leaf
+        .iter()
+        .map(|(id, data)| LeafRow {
+            node_id: *id,
+            packets_sent: data.packets_sent,
+            data_sent: data.data_sent.0,
+            msg_n: data.msg_n,
+            packet_types: PacketTypeRow::from(&data.packet_types),
+        })
+        .collect();
+    leaves.sort_by_key(|row| row.node_id);
+
+    let mut sessions: Vec<SessionRow> = info
+        .leaf
+        .iter()
+        .flat_map(|(id, data)| {
+            data.messages
+                .iter()
+                .map(|(session, (message, dest, ended))| SessionRow {
+                    node_id: *id,
+                    session: *session,
+                    destination: *dest,
+                    completed: *ended,
+                    message: format!("{message:?}"),
+                })
+        })
+        .collect();
+    sessions.sort_by_key(|row| (row.node_id, row.session));
+
+    StatsExport {
+        drones,
+        leaves,
+        sessions,
+    }
+}
+
+pub fn write_export(info: &DisplayedInfo) -> Result<PathBuf, String> {
+    let millis = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_err(|err| err.to_string())?
+        .as_millis();
+    // Exports made in the same millisecond get a counter instead of overwriting each other
+    let mut dir = Path::new(EXPORT_DIR).join(millis.to_string());
+    let mut count = 1;
+    while dir.exists() {
+        dir = Path::new(EXPORT_DIR).join(format!("{millis}-{count}"));
+        count += 1;
+    }
+    fs::create_dir_all(&dir).map_err(|err| err.to_string())?;
+
+    let stats = collect(info);
+    let json = serde_json::to_string_pretty(&stats).map_err(|err| err.to_string())?;
+    fs::write(dir.join("stats.json"), json).map_err(|err| err.to_string())?;
+
+    let mut drones = format!(
+        "node_id,packets_sent,packets_shortcutted,data_sent,data_dropped,fragments_dropped,neighbour_usage_percentages,{}\n",
+        PacketTypeRow::CSV_HEADER
+    );
+    for row in &stats.drones {
+        let usage: Vec<String> = row
+            .neighbour_usage_percentages
+            .iter()
+            .map(|(id, percentage)| format!("{id}:{percentage}"))
+            .collect();
+        drones.push_str(&format!(
+            "{},{},{},{},{},{},{},{}\n",
+            row.node_id,
+            row.packets_sent,
+            row.packets_shortcutted,
+            row.data_sent,
+            row.data_dropped,
+            row.fragments_dropped,
+            usage.join(" "),
+            row.packet_types.to_csv()
+        ));
+    }
+    fs::write(dir.join("drones.csv"), drones).map_err(|err| err.to_string())?;
+
+    let mut leaves = format!(
+        "node_id,packets_sent,data_sent,msg_n,{}\n",
+        PacketTypeRow::CSV_HEADER
+    );
+    for row in &stats.leaves {
+        leaves.push_str(&format!(
+            "{},{},{},{},{}\n",
+            row.node_id,
+            row.packets_sent,
+            row.data_sent,
+            row.msg_n,
+            row.packet_types.to_csv()
+        ));
+    }
+    fs::write(dir.join("leaves.csv"), leaves).map_err(|err| err.to_string())?;
+
+    let mut sessions = String::from("node_id,session,destination,completed,message\n");
+    for row in &stats.sessions {
+        sessions.push_str(&format!(
+            "{},{},{},{},{}\n",
+            row.node_id,
+            row.session,
+            row.destination,
+            row.completed,
+            csv_field(&row.message)
+        ));
+    }
+    fs::write(dir.join("sessions.csv"), sessions).map_err(|err| err.to_string())?;
+
+    Ok(dir)
+}
+
+fn csv_field(value: &str) -> String {
+    format!("\"{}\"", value.replace('"', "\"\""))
+}
@@ -0,0 +1,21 @@
+/// This module contains the flood plugin.
+/// It keeps track of every flood request seen by the controller, hop by hop,
+/// and shows a window that replays a single flood on the topology.
+mod resources;
+mod systems;
+
+use bevy::prelude::*;
+pub use resources::FloodTracker;
+use resources::FloodUiState;
+use systems::{flood_window, update_flood_highlight};
+
+pub struct FloodPlugin;
+
+impl Plugin for FloodPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(FloodTracker::default());
+        app.insert_resource(FloodUiState::default());
+        app.add_systems(Update, flood_window);
+        app.add_systems(Update, update_flood_highlight.after(flood_window));
+    }
+}
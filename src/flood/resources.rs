@@ -0,0 +1,146 @@
+use bevy::prelude::*;
+use std::collections::{HashMap, VecDeque};
+use wg_2024::network::NodeId;
+use wg_2024::packet::{FloodRequest, FloodResponse, NodeType};
+
+// Older floods are forgotten once this many are tracked
+const MAX_FLOODS: usize = 50;
+
+pub type FloodKey = (u64, NodeId);
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FloodStepKind {
+    // The node sent the request on to a neighbour
+    Forward,
+    // The node sent the request again with a different path, it should have stopped it
+    DuplicateForward,
+    // The node answered with a flood response instead of forwarding
+    Stop,
+}
+
+#[derive(Debug, Clone)]
+pub struct FloodStep {
+    pub time: f32,
+    pub node: NodeId,
+    pub hop: usize,
+    pub kind: FloodStepKind,
+}
+
+#[derive(Debug, Clone)]
+pub struct FloodRecord {
+    pub flood_id: u64,
+    pub initiator: NodeId,
+    pub started: f32,
+    pub steps: Vec<FloodStep>,
+    pub forwarded: HashMap<NodeId, u64>,
+    pub duplicates_forwarded: HashMap<NodeId, u64>,
+    pub stopped: HashMap<NodeId, u64>,
+    // Path traces carried back by the flood responses
+    pub responses: Vec<Vec<(NodeId, NodeType)>>,
+    // Path with which each node first forwarded the request
+    first_paths: HashMap<NodeId, Vec<NodeId>>,
+}
+
+impl FloodRecord {
+    fn new(flood_id: u64, initiator: NodeId, started: f32) -> Self {
+        FloodRecord {
+            flood_id,
+            initiator,
+            started,
+            steps: Vec::new(),
+            forwarded: HashMap::default(),
+            duplicates_forwarded: HashMap::default(),
+            stopped: HashMap::default(),
+            responses: Vec::new(),
+            first_paths: HashMap::default(),
+        }
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct FloodTracker {
+    pub floods: VecDeque<FloodRecord>,
+}
+
+impl FloodTracker {
+    pub fn get(&self, key: FloodKey) -> Option<&FloodRecord> {
+        self.floods
+            .iter()
+            .find(|f| (f.flood_id, f.initiator) == key)
+    }
+
+    fn entry(&mut self, key: FloodKey, time: f32) -> &mut FloodRecord {
+        if let Some(pos) = self
+            .floods
+            .iter()
+            .position(|f| (f.flood_id, f.initiator) == key)
+        {
+            return &mut self.floods[pos];
+        }
+        if self.floods.len() >= MAX_FLOODS {
+            self.floods.pop_front();
+        }
+        self.floods.push_back(FloodRecord::new(key.0, key.1, time));
+        self.floods.back_mut().unwrap()
+    }
+
+    /// Records a flood request sent by the last node of its path trace.
+    pub fn record_request(&mut self, request: &FloodRequest, time: f32) {
+        let Some((sender, _)) = request.path_trace.last() else {
+            return;
+        };
+        let path: Vec<NodeId> = request.path_trace.iter().map(|(id, _)| *id).collect();
+        let record = self.entry((request.flood_id, request.initiator_id), time);
+
+        let kind = match record.first_paths.get(sender) {
+            None => {
+                record.first_paths.insert(*sender, path.clone());
+                FloodStepKind::Forward
+            }
+            Some(first) if *first == path => FloodStepKind::Forward,
+            Some(_) => FloodStepKind::DuplicateForward,
+        };
+        if kind == FloodStepKind::DuplicateForward {
+            *record.duplicates_forwarded.entry(*sender).or_default() += 1;
+        }
+        *record.forwarded.entry(*sender).or_default() += 1;
+        record.steps.push(FloodStep {
+            time,
+            node: *sender,
+            hop: path.len() - 1,
+            kind,
+        });
+    }
+
+    /// Records a flood response, `generated` is true when it was just created by `sender`.
+    pub fn record_response(
+        &mut self,
+        response: &FloodResponse,
+        sender: NodeId,
+        generated: bool,
+        time: f32,
+    ) {
+        if !generated {
+            return;
+        }
+        let Some((initiator, _)) = response.path_trace.first() else {
+            return;
+        };
+        let record = self.entry((response.flood_id, *initiator), time);
+        *record.stopped.entry(sender).or_default() += 1;
+        record.responses.push(response.path_trace.clone());
+        record.steps.push(FloodStep {
+            time,
+            node: sender,
+            hop: response.path_trace.len().saturating_sub(1),
+            kind: FloodStepKind::Stop,
+        });
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct FloodUiState {
+    pub selected: Option<FloodKey>,
+    // Number of steps of the selected flood shown on the graph
+    pub replay_step: usize,
+}
@@ -0,0 +1,149 @@
+use super::resources::{FloodRecord, FloodStepKind, FloodTracker, FloodUiState};
+use crate::components::{FloodHighlight, Node};
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+use std::collections::{BTreeSet, HashMap};
+use wg_2024::network::NodeId;
+
+pub fn flood_window(
+    mut contexts: EguiContexts,
+    tracker: Res<FloodTracker>,
+    mut state: ResMut<FloodUiState>,
+) {
+    egui::Window::new("Floods")
+        .default_open(false)
+        .show(contexts.ctx_mut(), |ui| {
+            let selected_text = state
+                .selected
+                .map_or("None".to_string(), |(id, initiator)| {
+                    format!("Flood {id} from {initiator}")
+                });
+            egui::ComboBox::from_label("Flood")
+                .selected_text(selected_text)
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut state.selected, None, "None");
+                    for flood in tracker.floods.iter().rev() {
+                        let key = (flood.flood_id, flood.initiator);
+                        let label = format!(
+                            "Flood {} from {} ({} steps)",
+                            flood.flood_id,
+                            flood.initiator,
+                            flood.steps.len()
+                        );
+                        if ui
+                            .selectable_value(&mut state.selected, Some(key), label)
+                            .clicked()
+                        {
+                            state.replay_step = flood.steps.len();
+                        }
+                    }
+                });
+
+            let Some(flood) = state.selected.and_then(|key| tracker.get(key)) else {
+                ui.label("Select a flood to replay it on the graph");
+                return;
+            };
+            ui.add(
+                egui::Slider::new(&mut state.replay_step, 0..=flood.steps.len())
+                    .text("Replay step"),
+            );
+            if let Some(step) = state
+                .replay_step
+                .checked_sub(1)
+                .and_then(|i| flood.steps.get(i))
+            {
+                ui.label(format!(
+                    "+{:.3}s: node {} at hop {} ({:?})",
+                    step.time - flood.started,
+                    step.node,
+                    step.hop,
+                    step.kind
+                ));
+            }
+            ui.separator();
+            flood_stats_ui(ui, flood);
+            ui.separator();
+            ui.heading("Responses:");
+            egui::ScrollArea::vertical()
+                .max_height(200.0)
+                .show(ui, |ui| {
+                    for path in &flood.responses {
+                        let path: Vec<String> = path
+                            .iter()
+                            .map(|(id, kind)| format!("{id} ({kind:?})"))
+                            .collect();
+                        ui.label(path.join(" -> "));
+                    }
+                });
+        });
+}
+
+fn flood_stats_ui(ui: &mut egui::Ui, flood: &FloodRecord) {
+    let nodes: BTreeSet<NodeId> = flood
+        .forwarded
+        .keys()
+        .chain(flood.stopped.keys())
+        .copied()
+        .collect();
+    egui::Grid::new("flood_stats").striped(true).show(ui, |ui| {
+        ui.label("Node");
+        ui.label("Forwarded");
+        ui.label("Duplicates forwarded");
+        ui.label("Stopped");
+        ui.end_row();
+        for id in nodes {
+            ui.label(id.to_string());
+            ui.label(flood.forwarded.get(&id).unwrap_or(&0).to_string());
+            ui.label(
+                flood
+                    .duplicates_forwarded
+                    .get(&id)
+                    .unwrap_or(&0)
+                    .to_string(),
+            );
+            ui.label(flood.stopped.get(&id).unwrap_or(&0).to_string());
+            ui.end_row();
+        }
+    });
+}
+
+pub fn update_flood_highlight(
+    mut commands: Commands,
+    tracker: Res<FloodTracker>,
+    state: Res<FloodUiState>,
+    nodes: Query<(Entity, &Node, Option<&FloodHighlight>)>,
+) {
+    let mut colors: HashMap<NodeId, Color> = HashMap::new();
+    if let Some(flood) = state.selected.and_then(|key| tracker.get(key)) {
+        colors.insert(flood.initiator, Color::WHITE);
+        for step in flood.steps.iter().take(state.replay_step) {
+            let color = match step.kind {
+                FloodStepKind::Forward => hop_color(step.hop),
+                FloodStepKind::DuplicateForward => Color::srgb(1.0, 0.0, 0.0),
+                FloodStepKind::Stop => Color::srgb(0.2, 0.2, 0.2),
+            };
+            // A duplicate stays visible even if the node stops the flood later
+            if colors.get(&step.node) != Some(&Color::srgb(1.0, 0.0, 0.0)) {
+                colors.insert(step.node, color);
+            }
+        }
+    }
+    for (entity, node, highlight) in nodes.iter() {
+        match (colors.get(&node.id), highlight) {
+            (Some(color), Some(highlight)) if highlight.0 == *color => {}
+            (Some(color), _) => {
+                commands.entity(entity).insert(FloodHighlight(*color));
+            }
+            (None, Some(_)) => {
+                commands.entity(entity).remove::<FloodHighlight>();
+            }
+            (None, None) => {}
+        }
+    }
+}
+
+fn hop_color(hop: usize) -> Color {
+    // From yellow near the initiator to blue at the edge of the network
+    let t = (hop as f32 / 8.0).min(1.0);
+    Color::srgb(1.0 - t, 1.0 - t * 0.5, t)
+}
@@ -0,0 +1,142 @@
+use std::collections::{HashMap, HashSet};
+use wg_2024::network::NodeId;
+
+/// Finds the articulation points and the bridges of the graph with Tarjan's algorithm.
+/// Bridges are returned with the smallest id first, neighbours missing from the graph are ignored.
+pub fn articulation_points_and_bridges(
+    graph: &HashMap<NodeId, HashSet<NodeId>>,
+) -> (HashSet<NodeId>, HashSet<(NodeId, NodeId)>) {
+    let mut search = Search {
+        graph,
+        time: 0,
+        discovery: HashMap::new(),
+        low: HashMap::new(),
+        articulation_points: HashSet::new(),
+        bridges: HashSet::new(),
+    };
+    // Each connected component gets its own depth-first tree
+    let mut ids: Vec<NodeId> = graph.keys().copied().collect();
+    ids.sort_unstable();
+    for id in ids {
+        if !search.discovery.contains_key(&id) {
+            search.visit(id, None);
+        }
+    }
+    (search.articulation_points, search.bridges)
+}
+
+struct Search<'a> {
+    graph: &'a HashMap<NodeId, HashSet<NodeId>>,
+    time: usize,
+    // Order in which the nodes are reached by the depth-first search
+    discovery: HashMap<NodeId, usize>,
+    // Earliest node reachable from the subtree of a node through one back edge
+    low: HashMap<NodeId, usize>,
+    articulation_points: HashSet<NodeId>,
+    bridges: HashSet<(NodeId, NodeId)>,
+}
+
+impl Search<'_> {
+    fn visit(&mut self, id: NodeId, parent: Option<NodeId>) {
+        self.time += 1;
+        let discovery = self.time;
+        self.discovery.insert(id, discovery);
+        let mut low = discovery;
+        let mut children = 0;
+        let graph = self.graph;
+        for &next in &graph[&id] {
+            if Some(next) == parent || !graph.contains_key(&next) {
+                continue;
+            }
+            if let Some(&next_discovery) = self.discovery.get(&next) {
+                low = low.min(next_discovery);
+                continue;
+            }
+            children += 1;
+            self.visit(next, Some(id));
+            let next_low = self.low[&next];
+            low = low.min(next_low);
+            // The subtree of next can't reach above this node without going through it
+            if parent.is_some() && next_low >= discovery {
+                self.articulation_points.insert(id);
+            }
+            // Nor can it reach this node without the edge to next
+            if next_low > discovery {
+                self.bridges.insert((id.min(next), id.max(next)));
+            }
+        }
+        // The root only separates the graph when its subtrees are only joined by it
+        if parent.is_none() && children > 1 {
+            self.articulation_points.insert(id);
+        }
+        self.low.insert(id, low);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::articulation_points_and_bridges;
+    use std::collections::{HashMap, HashSet};
+    use wg_2024::network::NodeId;
+
+    fn graph(edges: &[(NodeId, NodeId)]) -> HashMap<NodeId, HashSet<NodeId>> {
+        let mut graph: HashMap<NodeId, HashSet<NodeId>> = HashMap::new();
+        for (a, b) in edges {
+            graph.entry(*a).or_default().insert(*b);
+            graph.entry(*b).or_default().insert(*a);
+        }
+        graph
+    }
+
+    fn set<T: std::hash::Hash + Eq + Clone>(items: &[T]) -> HashSet<T> {
+        items.iter().cloned().collect()
+    }
+
+    #[test]
+    fn chain_is_all_bridges() {
+        let (points, bridges) = articulation_points_and_bridges(&graph(&[(1, 2), (2, 3), (3, 4)]));
+        assert_eq!(points, set(&[2, 3]));
+        assert_eq!(bridges, set(&[(1, 2), (2, 3), (3, 4)]));
+    }
+
+    #[test]
+    fn cycle_has_nothing_fragile() {
+        let (points, bridges) =
+            articulation_points_and_bridges(&graph(&[(1, 2), (2, 3), (3, 4), (4, 1)]));
+        assert!(points.is_empty());
+        assert!(bridges.is_empty());
+    }
+
+    #[test]
+    fn pendant_hangs_on_one_point_and_bridge() {
+        let (points, bridges) =
+            articulation_points_and_bridges(&graph(&[(1, 2), (2, 3), (3, 1), (4, 1)]));
+        assert_eq!(points, set(&[1]));
+        assert_eq!(bridges, set(&[(1, 4)]));
+    }
+
+    #[test]
+    fn disconnected_parts_are_searched_separately() {
+        let (points, bridges) = articulation_points_and_bridges(&graph(&[
+            (1, 2),
+            (2, 3),
+            (4, 5),
+            (5, 6),
+            (6, 4),
+            (7, 6),
+        ]));
+        assert_eq!(points, set(&[2, 6]));
+        assert_eq!(bridges, set(&[(1, 2), (2, 3), (6, 7)]));
+    }
+
+    #[test]
+    fn neighbours_outside_the_graph_are_ignored() {
+        // A drone-only subgraph, with the leaf 9 still among the neighbours of 1 and 3
+        let mut drones = graph(&[(1, 2), (2, 3)]);
+        drones.get_mut(&1).unwrap().insert(9);
+        drones.get_mut(&3).unwrap().insert(9);
+        let (points, bridges) = articulation_points_and_bridges(&drones);
+        assert_eq!(points, set(&[2]));
+        assert_eq!(bridges, set(&[(1, 2), (2, 3)]));
+    }
+}
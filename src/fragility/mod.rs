@@ -0,0 +1,23 @@
+/// This module contains the fragility plugin.
+/// Whenever the topology changes it finds, with Tarjan's algorithm, the articulation points
+/// and the bridges of the network, and highlights the drones whose crash and the edges whose
+/// removal would disconnect it.
+mod algorithms;
+mod resources;
+mod systems;
+
+use bevy::prelude::*;
+use resources::Fragility;
+use systems::{find_fragile, fragility_window, highlight_fragile};
+
+pub struct FragilityPlugin;
+
+impl Plugin for FragilityPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Fragility::default());
+        app.add_systems(
+            Update,
+            (find_fragile, highlight_fragile, fragility_window).chain(),
+        );
+    }
+}
@@ -0,0 +1,29 @@
+use bevy::prelude::*;
+use std::collections::HashSet;
+use wg_2024::network::NodeId;
+
+#[derive(Resource)]
+pub struct Fragility {
+    pub enabled: bool,
+    // Drones whose crash disconnects the network
+    pub articulation_points: HashSet<NodeId>,
+    // Edges whose removal disconnects the network, smallest id first
+    pub bridges: HashSet<(NodeId, NodeId)>,
+}
+
+// On from the start, the orange gives way to the flood, step, invariant and search colours
+impl Default for Fragility {
+    fn default() -> Self {
+        Fragility {
+            enabled: true,
+            articulation_points: HashSet::default(),
+            bridges: HashSet::default(),
+        }
+    }
+}
+
+impl Fragility {
+    pub fn is_bridge(&self, a: NodeId, b: NodeId) -> bool {
+        self.bridges.contains(&(a.min(b), a.max(b)))
+    }
+}
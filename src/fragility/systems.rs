@@ -0,0 +1,100 @@
+use super::algorithms::articulation_points_and_bridges;
+use super::resources::Fragility;
+use crate::components::{Drone, Edge, FragileHighlight, Leaf, Node};
+use crate::settings::ModeConfig;
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+use std::collections::{HashMap, HashSet};
+use wg_2024::network::NodeId;
+
+// Runs Tarjan's algorithm again when a node is added, removed or changes neighbours
+pub fn find_fragile(
+    mut fragility: ResMut<Fragility>,
+    nodes: Query<(Ref<Node>, Has<Leaf>)>,
+    mut removed: RemovedComponents<Node>,
+    mode: Res<ModeConfig>,
+) {
+    let removed = removed.read().count() > 0;
+    if !removed && !mode.is_changed() && !nodes.iter().any(|(node, _)| node.is_changed()) {
+        return;
+    }
+    let topology: HashMap<NodeId, HashSet<NodeId>> = nodes
+        .iter()
+        .map(|(node, _)| (node.id, node.neighbours.clone()))
+        .collect();
+    // Like the crash check, only the drones have to stay connected unless the checks are
+    // bypassed; like the edge removal check, an edge must not disconnect any node
+    let drones: HashMap<NodeId, HashSet<NodeId>> = nodes
+        .iter()
+        .filter(|(_, leaf)| mode.bypass_cheks || !leaf)
+        .map(|(node, _)| (node.id, node.neighbours.clone()))
+        .collect();
+    (fragility.articulation_points, _) = articulation_points_and_bridges(&drones);
+    (_, fragility.bridges) = articulation_points_and_bridges(&topology);
+}
+
+pub fn highlight_fragile(
+    mut commands: Commands,
+    fragility: Res<Fragility>,
+    // Only drones can be crashed
+    drones: Query<(Entity, &Node, Has<FragileHighlight>), With<Drone>>,
+    edges: Query<(Entity, &Edge, Has<FragileHighlight>)>,
+) {
+    for (entity, node, highlighted) in drones.iter() {
+        let wanted = fragility.enabled && fragility.articulation_points.contains(&node.id);
+        if wanted && !highlighted {
+            commands.entity(entity).insert(FragileHighlight);
+        } else if !wanted && highlighted {
+            commands.entity(entity).remove::<FragileHighlight>();
+        }
+    }
+    for (entity, edge, highlighted) in edges.iter() {
+        let wanted = fragility.enabled && fragility.is_bridge(edge.start_node, edge.end_node);
+        if wanted && !highlighted {
+            commands.entity(entity).insert(FragileHighlight);
+        } else if !wanted && highlighted {
+            commands.entity(entity).remove::<FragileHighlight>();
+        }
+    }
+}
+
+pub fn fragility_window(
+    mut contexts: EguiContexts,
+    mut fragility: ResMut<Fragility>,
+    drones: Query<&Node, With<Drone>>,
+) {
+    egui::Window::new("Fragility")
+        .default_open(false)
+        .show(contexts.ctx_mut(), |ui| {
+            ui.checkbox(
+                &mut fragility.enabled,
+                "Highlight what would disconnect the network",
+            );
+            ui.separator();
+
+            let mut points: Vec<NodeId> = drones
+                .iter()
+                .map(|node| node.id)
+                .filter(|id| fragility.articulation_points.contains(id))
+                .collect();
+            points.sort_unstable();
+            ui.label("Drones whose crash disconnects it:");
+            if points.is_empty() {
+                ui.label("none");
+            } else {
+                let points: Vec<String> = points.iter().map(ToString::to_string).collect();
+                ui.label(points.join(", "));
+            }
+
+            let mut bridges: Vec<&(NodeId, NodeId)> = fragility.bridges.iter().collect();
+            bridges.sort_unstable();
+            ui.label("Edges whose removal disconnects it:");
+            if bridges.is_empty() {
+                ui.label("none");
+            } else {
+                let bridges: Vec<String> =
+                    bridges.iter().map(|(a, b)| format!("{a}-{b}")).collect();
+                ui.label(bridges.join(", "));
+            }
+        });
+}
@@ -0,0 +1,20 @@
+/// This module contains the heatmap plugin.
+/// It samples the bytes sent on each edge, in each direction, and colours and thickens
+/// the edges according to their recent traffic.
+mod resources;
+mod systems;
+
+use bevy::prelude::*;
+pub use resources::EdgeHeat;
+use systems::{color_edges, heatmap_window, sample_traffic};
+
+pub struct HeatmapPlugin;
+
+impl Plugin for HeatmapPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(EdgeHeat::default());
+        app.add_systems(FixedUpdate, sample_traffic);
+        app.add_systems(Update, color_edges);
+        app.add_systems(Update, heatmap_window);
+    }
+}
@@ -0,0 +1,82 @@
+use bevy::prelude::*;
+use std::collections::HashMap;
+use wg_2024::network::NodeId;
+
+// Weight of the last sample in the moving average of the traffic
+const SMOOTHING: f32 = 0.5;
+pub const MAX_WIDTH: f32 = 8.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HeatScale {
+    Linear,
+    Log,
+}
+
+#[derive(Resource)]
+pub struct EdgeHeat {
+    pub enabled: bool,
+    pub scale: HeatScale,
+    // Bytes sent so far from the first node to the second one
+    totals: HashMap<(NodeId, NodeId), u64>,
+    // Recent traffic in bytes per second, from the first node to the second one
+    pub rates: HashMap<(NodeId, NodeId), f32>,
+    pub max_rate: f32,
+}
+
+impl Default for EdgeHeat {
+    fn default() -> Self {
+        EdgeHeat {
+            enabled: true,
+            scale: HeatScale::Linear,
+            totals: HashMap::default(),
+            rates: HashMap::default(),
+            max_rate: 0.0,
+        }
+    }
+}
+
+impl EdgeHeat {
+    /// Updates the rates with the byte counters sampled `secs` after the previous ones.
+    pub fn sample(&mut self, counters: &HashMap<(NodeId, NodeId), u64>, secs: f32) {
+        for (link, total) in counters {
+            let previous = self.totals.insert(*link, *total).unwrap_or(0);
+            // Counters going back means the infos were reset
+            let delta = total.saturating_sub(previous) as f32 / secs;
+            let rate = self.rates.entry(*link).or_default();
+            *rate = SMOOTHING * delta + (1.0 - SMOOTHING) * *rate;
+        }
+        for (link, rate) in &mut self.rates {
+            if !counters.contains_key(link) {
+                *rate *= 1.0 - SMOOTHING;
+            }
+        }
+        self.max_rate = self.rates.values().copied().fold(0.0, f32::max);
+    }
+
+    pub fn rate(&self, from: NodeId, to: NodeId) -> f32 {
+        self.rates.get(&(from, to)).copied().unwrap_or(0.0)
+    }
+
+    /// Position of a rate between 0 and the highest rate, according to the scale.
+    pub fn level(&self, rate: f32) -> f32 {
+        if self.max_rate <= 0.0 {
+            return 0.0;
+        }
+        match self.scale {
+            HeatScale::Linear => rate / self.max_rate,
+            HeatScale::Log => rate.ln_1p() / self.max_rate.ln_1p(),
+        }
+        .clamp(0.0, 1.0)
+    }
+}
+
+pub fn heat_color(level: f32) -> Color {
+    // Grey when idle, then yellow up to red on the busiest links
+    if level <= 0.0 {
+        Color::srgb(0.6, 0.6, 0.6)
+    } else if level < 0.5 {
+        Color::srgb(1.0, 1.0, 1.0 - level * 2.0)
+    } else {
+        Color::srgb(1.0, 2.0 - level * 2.0, 0.0)
+    }
+}
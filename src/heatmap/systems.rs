@@ -0,0 +1,106 @@
+use super::resources::{heat_color, EdgeHeat, HeatScale, MAX_WIDTH};
+use crate::components::{
+    Dimmed, Edge, EdgeLane, FragileHighlight, StepHighlight, DIM_EDGE_COLOR, FRAGILE_COLOR,
+    STEP_COLOR,
+};
+use crate::core::creator::EDGE_COLOR;
+use crate::event_listener::DisplayedInfo;
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+use std::collections::HashMap;
+
+pub fn sample_traffic(info: Res<DisplayedInfo>, mut heat: ResMut<EdgeHeat>, time: Res<Time>) {
+    let mut counters = HashMap::new();
+    for (id, data) in &info.drone {
+        for (ngb, bytes) in &data.neighbours {
+            counters.insert((*id, *ngb), bytes.0);
+        }
+    }
+    for (id, data) in &info.leaf {
+        for (ngb, bytes) in &data.neighbours {
+            counters.insert((*id, *ngb), bytes.0);
+        }
+    }
+    heat.sample(&counters, time.delta_secs().max(f32::EPSILON));
+}
+
+pub fn color_edges(
+    heat: Res<EdgeHeat>,
+    mut edges: Query<(
+        &Edge,
+        &Children,
+        &mut Transform,
+        Has<StepHighlight>,
+        Has<FragileHighlight>,
+        Has<Dimmed>,
+    )>,
+    mut lanes: Query<(&EdgeLane, &mut Sprite)>,
+) {
+    for (edge, children, mut transform, step, fragile, dimmed) in &mut edges {
+        let forward = heat.rate(edge.start_node, edge.end_node);
+        let backward = heat.rate(edge.end_node, edge.start_node);
+        let width = if heat.enabled {
+            1.0 + (MAX_WIDTH - 1.0) * heat.level(forward + backward)
+        } else {
+            1.0
+        };
+        if (transform.scale.y - width).abs() > f32::EPSILON {
+            transform.scale.y = width;
+        }
+        for child in children.iter() {
+            let Ok((lane, mut sprite)) = lanes.get_mut(*child) else {
+                continue;
+            };
+            let color = match (heat.enabled, lane.forward) {
+                _ if step => STEP_COLOR,
+                _ if dimmed => DIM_EDGE_COLOR,
+                _ if fragile => FRAGILE_COLOR,
+                (false, _) => EDGE_COLOR,
+                (true, true) => heat_color(heat.level(forward)),
+                (true, false) => heat_color(heat.level(backward)),
+            };
+            if sprite.color != color {
+                sprite.color = color;
+            }
+        }
+    }
+}
+
+pub fn heatmap_window(mut contexts: EguiContexts, mut heat: ResMut<EdgeHeat>) {
+    egui::Window::new("Heatmap")
+        .default_open(false)
+        .show(contexts.ctx_mut(), |ui| {
+            ui.checkbox(&mut heat.enabled, "Colour edges by traffic");
+            ui.horizontal(|ui| {
+                ui.label("Scale:");
+                ui.selectable_value(&mut heat.scale, HeatScale::Linear, "Linear");
+                ui.selectable_value(&mut heat.scale, HeatScale::Log, "Log");
+            });
+            ui.separator();
+            ui.label("Each edge shows one lane per direction, thickness is the total");
+            let (rect, _) = ui.allocate_exact_size(egui::vec2(200.0, 16.0), egui::Sense::hover());
+            let steps = 20;
+            for i in 0..steps {
+                let level = i as f32 / (steps - 1) as f32;
+                let color = heat_color(level).to_srgba();
+                let x = rect.left() + rect.width() * i as f32 / steps as f32;
+                ui.painter().rect_filled(
+                    egui::Rect::from_min_size(
+                        egui::pos2(x, rect.top()),
+                        egui::vec2(rect.width() / steps as f32 + 1.0, rect.height()),
+                    ),
+                    0.0,
+                    egui::Color32::from_rgb(
+                        (color.red * 255.0) as u8,
+                        (color.green * 255.0) as u8,
+                        (color.blue * 255.0) as u8,
+                    ),
+                );
+            }
+            ui.horizontal(|ui| {
+                ui.label("idle");
+                ui.add_space(120.0);
+                ui.label(format!("{:.0} B/s", heat.max_rate));
+            });
+        });
+}
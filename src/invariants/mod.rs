@@ -0,0 +1,21 @@
+/// This module contains the invariants plugin.
+/// Users write assertions over the live state, in the invariants window or in a file,
+/// and when one of them fails the controller stops ingesting events, saves a snapshot
+/// and highlights the nodes involved.
+mod resources;
+mod systems;
+
+use bevy::prelude::*;
+pub use resources::InvariantsState;
+use systems::{check_invariants, invariants_window, load_invariants_file};
+
+pub struct InvariantsPlugin;
+
+impl Plugin for InvariantsPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(InvariantsState::default());
+        app.add_systems(Startup, load_invariants_file);
+        app.add_systems(FixedUpdate, check_invariants);
+        app.add_systems(Update, invariants_window);
+    }
+}
@@ -0,0 +1,140 @@
+use bevy::prelude::*;
+use std::fmt::Display;
+use wg_2024::network::NodeId;
+
+pub const INVARIANTS_FILE: &str = "invariants.txt";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Metric {
+    // Fragments dropped over fragments handled by a drone
+    DropRatio,
+    Queue,
+    Neighbours,
+    Nacks,
+    // Age in seconds of the oldest message a leaf is still sending
+    SessionSecs,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Selector {
+    Node(NodeId),
+    All,
+    Drones,
+    Clients,
+    Servers,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Op {
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Eq,
+    Ne,
+}
+
+impl Op {
+    pub fn holds(self, left: f64, right: f64) -> bool {
+        match self {
+            Op::Lt => left < right,
+            Op::Le => left <= right,
+            Op::Gt => left > right,
+            Op::Ge => left >= right,
+            Op::Eq => (left - right).abs() < f64::EPSILON,
+            Op::Ne => (left - right).abs() >= f64::EPSILON,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Invariant {
+    pub text: String,
+    pub metric: Metric,
+    pub selector: Selector,
+    pub op: Op,
+    pub value: f64,
+    // A violated invariant is not checked again until it is re-armed
+    pub triggered: bool,
+}
+
+impl Invariant {
+    /// Parses an invariant written as `metric(selector) op value`, e.g. `queue(*) <= 50`.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let text = text.trim();
+        let ops = [
+            ("<=", Op::Le),
+            (">=", Op::Ge),
+            ("==", Op::Eq),
+            ("!=", Op::Ne),
+            ("<", Op::Lt),
+            (">", Op::Gt),
+        ];
+        let Some((pos, symbol, op)) = ops
+            .iter()
+            .find_map(|(symbol, op)| text.find(symbol).map(|pos| (pos, *symbol, *op)))
+        else {
+            return Err("missing comparison operator".to_string());
+        };
+        let left = text[..pos].trim();
+        let value = text[pos + symbol.len()..]
+            .trim()
+            .parse::<f64>()
+            .map_err(|_| "right side must be a number".to_string())?;
+
+        let Some((metric, selector)) = left.strip_suffix(')').and_then(|left| left.split_once('('))
+        else {
+            return Err("left side must look like metric(selector)".to_string());
+        };
+        let metric = match metric.trim() {
+            "drop_ratio" => Metric::DropRatio,
+            "queue" => Metric::Queue,
+            "neighbours" => Metric::Neighbours,
+            "nacks" => Metric::Nacks,
+            "session_secs" => Metric::SessionSecs,
+            other => return Err(format!("unknown metric {other}")),
+        };
+        let selector = match selector.trim() {
+            "*" => Selector::All,
+            "drone" => Selector::Drones,
+            "client" => Selector::Clients,
+            "server" => Selector::Servers,
+            id => Selector::Node(
+                id.parse::<NodeId>()
+                    .map_err(|_| format!("unknown selector {id}"))?,
+            ),
+        };
+        Ok(Invariant {
+            text: text.to_string(),
+            metric,
+            selector,
+            op,
+            value,
+            triggered: false,
+        })
+    }
+}
+
+impl Display for Invariant {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.text)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Violation {
+    pub time: f32,
+    pub invariant: String,
+    // Nodes failing the invariant with their value
+    pub nodes: Vec<(NodeId, f64)>,
+    pub snapshot: Option<String>,
+}
+
+#[derive(Resource, Default)]
+pub struct InvariantsState {
+    pub invariants: Vec<Invariant>,
+    pub input: String,
+    pub file: String,
+    pub error: Option<String>,
+    pub violations: Vec<Violation>,
+}
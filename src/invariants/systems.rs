@@ -0,0 +1,270 @@
+use super::resources::{Invariant, InvariantsState, Metric, Selector, Violation, INVARIANTS_FILE};
+use crate::components::{Drone, InvariantHighlight, Leaf, LeafType, Node};
+use crate::event_listener::{DisplayedInfo, Ingestion};
+use crate::export::write_export;
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+use serde::Serialize;
+use std::fs;
+use wg_2024::network::NodeId;
+
+#[derive(Serialize)]
+struct NodeSnapshot {
+    node_id: NodeId,
+    node_type: String,
+    neighbours: Vec<NodeId>,
+    pdr: Option<f32>,
+    queue: usize,
+}
+
+#[derive(Serialize)]
+struct ViolationSnapshot<'a> {
+    time: f32,
+    invariant: &'a str,
+    failing: &'a [(NodeId, f64)],
+    topology: Vec<NodeSnapshot>,
+}
+
+pub fn load_invariants_file(mut state: ResMut<InvariantsState>) {
+    state.file = INVARIANTS_FILE.to_string();
+    if fs::metadata(INVARIANTS_FILE).is_ok() {
+        load_file(&mut state);
+    }
+}
+
+fn load_file(state: &mut InvariantsState) {
+    let content = match fs::read_to_string(&state.file) {
+        Ok(content) => content,
+        Err(err) => {
+            state.error = Some(format!("Can't read {}: {err}", state.file));
+            return;
+        }
+    };
+    state.error = None;
+    for line in content.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        match Invariant::parse(line) {
+            Ok(invariant) => state.invariants.push(invariant),
+            Err(err) => state.error = Some(format!("{line}: {err}")),
+        }
+    }
+}
+
+fn metric_value(
+    metric: Metric,
+    node: &Node,
+    drone: Option<&Drone>,
+    info: &DisplayedInfo,
+    now: f32,
+) -> Option<f64> {
+    match metric {
+        Metric::DropRatio => {
+            drone?;
+            let data = info.drone.get(&node.id);
+            let dropped = data.map_or(0, |d| d.fragments_dropped);
+            let sent = data.map_or(0, |d| d.packet_types.fragments);
+            if dropped + sent == 0 {
+                Some(0.0)
+            } else {
+                Some(dropped as f64 / (dropped + sent) as f64)
+            }
+        }
+        Metric::Queue => Some(node.packet_channel.len() as f64),
+        Metric::Neighbours => Some(node.neighbours.len() as f64),
+        Metric::Nacks => Some(
+            info.drone
+                .get(&node.id)
+                .map(|d| d.packet_types.nacks())
+                .or_else(|| info.leaf.get(&node.id).map(|l| l.packet_types.nacks()))
+                .unwrap_or(0) as f64,
+        ),
+        Metric::SessionSecs => {
+            if drone.is_some() {
+                return None;
+            }
+            let oldest = info.leaf.get(&node.id).and_then(|leaf| {
+                leaf.messages
+                    .iter()
+                    .filter(|(_, (_, _, ended))| !ended)
+                    .filter_map(|(session, _)| leaf.sessions_started.get(session))
+                    .copied()
+                    .reduce(f32::min)
+            });
+            Some(oldest.map_or(0.0, |started| f64::from(now - started)))
+        }
+    }
+}
+
+fn selected(selector: Selector, node: &Node, drone: Option<&Drone>, leaf: Option<&Leaf>) -> bool {
+    match selector {
+        Selector::Node(id) => node.id == id,
+        Selector::All => true,
+        Selector::Drones => drone.is_some(),
+        Selector::Clients => leaf.is_some_and(|leaf| leaf.leaf_type == LeafType::Client),
+        Selector::Servers => leaf.is_some_and(|leaf| leaf.leaf_type == LeafType::Server),
+    }
+}
+
+pub fn check_invariants(
+    mut commands: Commands,
+    mut state: ResMut<InvariantsState>,
+    mut ingestion: ResMut<Ingestion>,
+    info: Res<DisplayedInfo>,
+    nodes: Query<(Entity, &Node, Option<&Drone>, Option<&Leaf>)>,
+    time: Res<Time<Virtual>>,
+) {
+    if ingestion.paused {
+        return;
+    }
+    let now = time.elapsed_secs();
+    let state = &mut *state;
+    for invariant in state.invariants.iter_mut().filter(|inv| !inv.triggered) {
+        let mut failing = Vec::new();
+        for (entity, node, drone, leaf) in nodes.iter() {
+            if !selected(invariant.selector, node, drone, leaf) {
+                continue;
+            }
+            let Some(value) = metric_value(invariant.metric, node, drone, &info, now) else {
+                continue;
+            };
+            if !invariant.op.holds(value, invariant.value) {
+                failing.push((node.id, value));
+                commands.entity(entity).insert(InvariantHighlight);
+            }
+        }
+        if failing.is_empty() {
+            continue;
+        }
+
+        invariant.triggered = true;
+        ingestion.paused = true;
+        println!("Invariant violated: {invariant} by {failing:?}, event ingestion paused");
+        let snapshot = snapshot(&invariant.text, &failing, &info, &nodes, now);
+        if let Err(err) = &snapshot {
+            eprintln!("Error saving the snapshot: {err}");
+        }
+        state.violations.push(Violation {
+            time: now,
+            invariant: invariant.text.clone(),
+            nodes: failing,
+            snapshot: snapshot.ok(),
+        });
+    }
+}
+
+fn snapshot(
+    invariant: &str,
+    failing: &[(NodeId, f64)],
+    info: &DisplayedInfo,
+    nodes: &Query<(Entity, &Node, Option<&Drone>, Option<&Leaf>)>,
+    now: f32,
+) -> Result<String, String> {
+    let dir = write_export(info)?;
+    let mut topology: Vec<NodeSnapshot> = nodes
+        .iter()
+        .map(|(_, node, drone, leaf)| {
+            let mut neighbours: Vec<NodeId> = node.neighbours.iter().copied().collect();
+            neighbours.sort_unstable();
+            NodeSnapshot {
+                node_id: node.id,
+                node_type: leaf.map_or("Drone".to_string(), |leaf| leaf.leaf_type.to_string()),
+                neighbours,
+                pdr: drone.map(|drone| drone.pdr),
+                queue: node.packet_channel.len(),
+            }
+        })
+        .collect();
+    topology.sort_by_key(|node| node.node_id);
+    let snapshot = ViolationSnapshot {
+        time: now,
+        invariant,
+        failing,
+        topology,
+    };
+    let json = serde_json::to_string_pretty(&snapshot).map_err(|err| err.to_string())?;
+    fs::write(dir.join("violation.json"), json).map_err(|err| err.to_string())?;
+    Ok(dir.display().to_string())
+}
+
+pub fn invariants_window(
+    mut commands: Commands,
+    mut contexts: EguiContexts,
+    mut state: ResMut<InvariantsState>,
+    mut ingestion: ResMut<Ingestion>,
+    highlighted: Query<Entity, With<InvariantHighlight>>,
+) {
+    egui::Window::new("Invariants")
+        .default_open(false)
+        .show(contexts.ctx_mut(), |ui| {
+            let state = &mut *state;
+            ui.label("Write metric(selector) op value, metrics: drop_ratio, queue, neighbours, nacks, session_secs; selectors: id, *, drone, client, server");
+            ui.horizontal(|ui| {
+                ui.text_edit_singleline(&mut state.input);
+                if ui.button("Add").clicked() {
+                    match Invariant::parse(&state.input) {
+                        Ok(invariant) => {
+                            state.invariants.push(invariant);
+                            state.input.clear();
+                            state.error = None;
+                        }
+                        Err(err) => state.error = Some(err),
+                    }
+                }
+            });
+            ui.horizontal(|ui| {
+                ui.label("File:");
+                ui.text_edit_singleline(&mut state.file);
+                if ui.button("Load").clicked() {
+                    load_file(state);
+                }
+            });
+            if let Some(err) = &state.error {
+                ui.colored_label(egui::Color32::LIGHT_RED, err);
+            }
+            ui.separator();
+
+            let mut removed = None;
+            for (i, invariant) in state.invariants.iter_mut().enumerate() {
+                ui.horizontal(|ui| {
+                    if invariant.triggered {
+                        ui.colored_label(egui::Color32::LIGHT_RED, &invariant.text);
+                        if ui.button("Re-arm").clicked() {
+                            invariant.triggered = false;
+                        }
+                    } else {
+                        ui.label(&invariant.text);
+                    }
+                    if ui.button("Remove").clicked() {
+                        removed = Some(i);
+                    }
+                });
+            }
+            if let Some(i) = removed {
+                state.invariants.remove(i);
+            }
+            ui.separator();
+
+            if ingestion.paused {
+                ui.colored_label(egui::Color32::LIGHT_RED, "Event ingestion paused");
+                if ui.button("Resume").clicked() {
+                    ingestion.paused = false;
+                    for entity in highlighted.iter() {
+                        commands.entity(entity).remove::<InvariantHighlight>();
+                    }
+                }
+            }
+            ui.heading("Violations:");
+            for violation in state.violations.iter().rev() {
+                ui.label(format!(
+                    "[{:.1}s] {} failed on {:?}",
+                    violation.time, violation.invariant, violation.nodes
+                ));
+                if let Some(dir) = &violation.snapshot {
+                    ui.label(format!("Snapshot: {dir}"));
+                }
+            }
+        });
+}
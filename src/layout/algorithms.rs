@@ -0,0 +1,218 @@
+use bevy::prelude::*;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::f32::consts::TAU;
+use wg_2024::network::NodeId;
+
+// Distance between two neighbouring nodes in every layout
+const SPACING: f32 = 90.0;
+// Sweeps of the stress majorization, the graphs are small enough to converge well before
+const STRESS_ITERATIONS: usize = 200;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Role {
+    Client,
+    Drone,
+    Server,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Topology {
+    // Sorted by role and then by id, so that every layout is stable
+    pub nodes: Vec<(NodeId, Role)>,
+    pub neighbours: HashMap<NodeId, HashSet<NodeId>>,
+}
+
+impl Topology {
+    fn sorted_neighbours(&self, id: NodeId) -> Vec<NodeId> {
+        let mut neighbours: Vec<NodeId> = self
+            .neighbours
+            .get(&id)
+            .map(|ngbs| ngbs.iter().copied().collect())
+            .unwrap_or_default();
+        neighbours.sort_unstable();
+        neighbours
+    }
+
+    // Breadth first visit starting from the first node, so that neighbours end up close
+    fn bfs_order(&self) -> Vec<NodeId> {
+        let mut order = Vec::with_capacity(self.nodes.len());
+        let mut visited = HashSet::new();
+        for (start, _) in &self.nodes {
+            if !visited.insert(*start) {
+                continue;
+            }
+            let mut queue = VecDeque::from([*start]);
+            while let Some(id) = queue.pop_front() {
+                order.push(id);
+                for ngb in self.sorted_neighbours(id) {
+                    if visited.insert(ngb) {
+                        queue.push_back(ngb);
+                    }
+                }
+            }
+        }
+        order
+    }
+
+    // Hops from `start` to every node reachable from it
+    fn hops_from(&self, start: NodeId) -> HashMap<NodeId, usize> {
+        let mut hops = HashMap::from([(start, 0)]);
+        let mut queue = VecDeque::from([start]);
+        while let Some(id) = queue.pop_front() {
+            let next = hops[&id] + 1;
+            for ngb in self.sorted_neighbours(id) {
+                hops.entry(ngb).or_insert_with(|| {
+                    queue.push_back(ngb);
+                    next
+                });
+            }
+        }
+        hops
+    }
+}
+
+/// Clients on the left, servers on the right and each drone in the column of its hop distance
+/// from the closest client. Nodes in a column are ordered by the position of their neighbours
+/// in the previous one, which removes most crossings.
+#[allow(clippy::cast_precision_loss)]
+pub fn layered(topology: &Topology) -> HashMap<NodeId, Vec2> {
+    if topology.nodes.is_empty() {
+        return HashMap::new();
+    }
+    let role: HashMap<NodeId, Role> = topology.nodes.iter().copied().collect();
+    // Packets only go through drones, so the distance is only propagated through them
+    let mut depth: HashMap<NodeId, usize> = HashMap::new();
+    let mut queue = VecDeque::new();
+    for (id, _) in topology.nodes.iter().filter(|(_, r)| *r == Role::Client) {
+        depth.insert(*id, 0);
+        queue.push_back(*id);
+    }
+    while let Some(id) = queue.pop_front() {
+        let next = depth[&id] + 1;
+        for ngb in topology.sorted_neighbours(id) {
+            if role.get(&ngb) == Some(&Role::Drone) && !depth.contains_key(&ngb) {
+                depth.insert(ngb, next);
+                queue.push_back(ngb);
+            }
+        }
+    }
+    let deepest = depth.values().copied().max().unwrap_or(0);
+    let unreachable = deepest + 1;
+    let mut columns: Vec<Vec<NodeId>> = vec![Vec::new(); deepest + 3];
+    for (id, role) in &topology.nodes {
+        let column = match role {
+            Role::Server => unreachable + 1,
+            _ => depth.get(id).copied().unwrap_or(unreachable),
+        };
+        columns[column].push(*id);
+    }
+    columns.retain(|column| !column.is_empty());
+
+    let mut rank: HashMap<NodeId, f32> = HashMap::new();
+    for column in &mut columns {
+        let barycenter = |id: &NodeId| {
+            let ranks: Vec<f32> = topology
+                .sorted_neighbours(*id)
+                .iter()
+                .filter_map(|ngb| rank.get(ngb).copied())
+                .collect();
+            if ranks.is_empty() {
+                f32::MAX
+            } else {
+                ranks.iter().sum::<f32>() / ranks.len() as f32
+            }
+        };
+        column.sort_by(|a, b| barycenter(a).total_cmp(&barycenter(b)));
+        for (i, id) in column.iter().enumerate() {
+            rank.insert(*id, i as f32);
+        }
+    }
+
+    let width = (columns.len() - 1) as f32;
+    let mut positions = HashMap::new();
+    for (x, column) in columns.iter().enumerate() {
+        let height = (column.len() - 1) as f32;
+        for (y, id) in column.iter().enumerate() {
+            let position = Vec2::new(x as f32 - width / 2.0, height / 2.0 - y as f32);
+            positions.insert(*id, position * Vec2::new(SPACING * 1.5, SPACING));
+        }
+    }
+    positions
+}
+
+/// Every node on a circle, in breadth first order.
+#[allow(clippy::cast_precision_loss)]
+pub fn circular(topology: &Topology) -> HashMap<NodeId, Vec2> {
+    let order = topology.bfs_order();
+    let count = order.len() as f32;
+    let radius = (SPACING * count / TAU).max(SPACING);
+    order
+        .into_iter()
+        .enumerate()
+        .map(|(i, id)| {
+            let angle = TAU * i as f32 / count;
+            (id, Vec2::from_angle(angle) * radius)
+        })
+        .collect()
+}
+
+/// Rows of nodes filling a square, in breadth first order.
+#[allow(clippy::cast_precision_loss)]
+#[allow(clippy::cast_possible_truncation)]
+#[allow(clippy::cast_sign_loss)]
+pub fn grid(topology: &Topology) -> HashMap<NodeId, Vec2> {
+    let order = topology.bfs_order();
+    let columns = (order.len() as f32).sqrt().ceil().max(1.0) as usize;
+    let rows = order.len().div_ceil(columns);
+    let offset = Vec2::new((columns - 1) as f32, rows.saturating_sub(1) as f32) / 2.0;
+    order
+        .into_iter()
+        .enumerate()
+        .map(|(i, id)| {
+            let cell = Vec2::new((i % columns) as f32, -((i / columns) as f32));
+            (id, (cell - offset * Vec2::new(1.0, -1.0)) * SPACING)
+        })
+        .collect()
+}
+
+/// Kamada-Kawai layout: the distance between two nodes on screen follows their hop distance.
+/// The energy is minimised with stress majorization, which unlike the original Newton-Raphson
+/// method can't get stuck oscillating, starting from the circular layout.
+#[allow(clippy::cast_precision_loss)]
+pub fn kamada_kawai(topology: &Topology) -> HashMap<NodeId, Vec2> {
+    let ids: Vec<NodeId> = topology.nodes.iter().map(|(id, _)| *id).collect();
+    let hops: Vec<HashMap<NodeId, usize>> = ids.iter().map(|id| topology.hops_from(*id)).collect();
+    // Disconnected nodes are kept a bit further than the farthest connected ones
+    let farthest = hops
+        .iter()
+        .flat_map(|hops| hops.values())
+        .copied()
+        .max()
+        .unwrap_or(0);
+    let distance =
+        |i: usize, j: usize| hops[i].get(&ids[j]).copied().unwrap_or(farthest + 1) as f32 * SPACING;
+
+    let start = circular(topology);
+    let mut positions: Vec<Vec2> = ids.iter().map(|id| start[id]).collect();
+    for _ in 0..STRESS_ITERATIONS {
+        for i in 0..ids.len() {
+            let mut sum = Vec2::ZERO;
+            let mut weights = 0.0;
+            for j in (0..ids.len()).filter(|j| *j != i) {
+                let d = distance(i, j);
+                let weight = 1.0 / (d * d);
+                let delta = positions[i] - positions[j];
+                let direction = delta.try_normalize().unwrap_or(Vec2::X);
+                sum += weight * (positions[j] + d * direction);
+                weights += weight;
+            }
+            if weights > 0.0 {
+                positions[i] = sum / weights;
+            }
+        }
+    }
+    let centroid = positions.iter().sum::<Vec2>() / positions.len().max(1) as f32;
+    ids.into_iter()
+        .zip(positions.into_iter().map(|position| position - centroid))
+        .collect()
+}
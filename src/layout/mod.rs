@@ -0,0 +1,21 @@
+/// This module contains the layout plugin.
+/// Besides the force simulation the nodes can be laid out in layers (clients, drones by hop
+/// distance, servers), on a circle, on a grid or with Kamada-Kawai. Switching layout animates
+/// the nodes to their new place, and any layout can be frozen.
+mod algorithms;
+mod resources;
+mod systems;
+
+use bevy::prelude::*;
+use resources::LayoutState;
+pub use systems::simulation_running;
+use systems::{animate_layout, layout_window, plan_layout};
+
+pub struct LayoutPlugin;
+
+impl Plugin for LayoutPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(LayoutState::default());
+        app.add_systems(Update, (layout_window, plan_layout, animate_layout).chain());
+    }
+}
@@ -0,0 +1,65 @@
+use super::algorithms::{circular, grid, kamada_kawai, layered, Topology};
+use bevy::prelude::*;
+use std::collections::HashMap;
+use wg_2024::network::NodeId;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LayoutKind {
+    #[default]
+    Force,
+    Layered,
+    Circular,
+    Grid,
+    KamadaKawai,
+}
+
+impl LayoutKind {
+    pub const ALL: [LayoutKind; 5] = [
+        LayoutKind::Force,
+        LayoutKind::Layered,
+        LayoutKind::Circular,
+        LayoutKind::Grid,
+        LayoutKind::KamadaKawai,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            LayoutKind::Force => "Force",
+            LayoutKind::Layered => "Layered",
+            LayoutKind::Circular => "Circular",
+            LayoutKind::Grid => "Grid",
+            LayoutKind::KamadaKawai => "Kamada-Kawai",
+        }
+    }
+
+    /// Positions of the nodes around the origin, `None` for the force simulation.
+    pub fn positions(self, topology: &Topology) -> Option<HashMap<NodeId, Vec2>> {
+        match self {
+            LayoutKind::Force => None,
+            LayoutKind::Layered => Some(layered(topology)),
+            LayoutKind::Circular => Some(circular(topology)),
+            LayoutKind::Grid => Some(grid(topology)),
+            LayoutKind::KamadaKawai => Some(kamada_kawai(topology)),
+        }
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct LayoutState {
+    pub kind: LayoutKind,
+    // A frozen layout doesn't move the nodes by itself anymore
+    pub frozen: bool,
+    // Set to lay the nodes out again even if the topology didn't change
+    pub dirty: bool,
+    // Topology of the last layout, to notice nodes and edges added or removed
+    pub topology: Topology,
+    // Where the nodes are being animated to
+    pub targets: HashMap<Entity, Vec2>,
+}
+
+impl LayoutState {
+    /// Whether the force simulation is moving the nodes.
+    pub fn simulating(&self) -> bool {
+        self.kind == LayoutKind::Force && !self.frozen
+    }
+}
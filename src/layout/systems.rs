@@ -0,0 +1,135 @@
+use super::algorithms::{Role, Topology};
+use super::resources::{LayoutKind, LayoutState};
+use crate::components::{Leaf, LeafType, Node};
+use crate::physics::{MyForceGraph, NodeForceGraphMarker, Pinned};
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+use std::collections::HashMap;
+
+// How fast the nodes reach their place, higher is faster
+const ANIMATION_SPEED: f32 = 6.0;
+// Closer than this to their target the nodes are considered arrived
+const ARRIVED: f32 = 0.5;
+
+pub fn simulation_running(state: Res<LayoutState>) -> bool {
+    state.simulating()
+}
+
+pub fn layout_window(
+    mut contexts: EguiContexts,
+    mut state: ResMut<LayoutState>,
+    mut force_graph: ResMut<MyForceGraph>,
+) {
+    egui::Window::new("Layout")
+        .default_open(false)
+        .show(contexts.ctx_mut(), |ui| {
+            let before = (state.kind, state.frozen);
+            egui::ComboBox::from_label("Algorithm")
+                .selected_text(state.kind.name())
+                .show_ui(ui, |ui| {
+                    for kind in LayoutKind::ALL {
+                        ui.selectable_value(&mut state.kind, kind, kind.name());
+                    }
+                });
+            ui.checkbox(&mut state.frozen, "Freeze").on_hover_text(
+                "Stop the simulation, or stop laying the nodes out again when the topology changes",
+            );
+            if state.kind != before.0 {
+                state.dirty = true;
+            }
+            if (state.kind, state.frozen) != before {
+                force_graph.wake();
+            }
+            if state.kind != LayoutKind::Force && ui.button("Lay out again").clicked() {
+                state.dirty = true;
+            }
+        });
+}
+
+// Computes where the nodes should go when the layout is picked, or when the topology changes
+pub fn plan_layout(
+    mut state: ResMut<LayoutState>,
+    force_graph: Res<MyForceGraph>,
+    nodes: Query<(Entity, &Node, Option<&Leaf>, Has<Pinned>)>,
+) {
+    if state.kind == LayoutKind::Force {
+        state.targets.clear();
+        state.dirty = false;
+        return;
+    }
+    let mut topology = Topology::default();
+    for (_, node, leaf, _) in &nodes {
+        let role = match leaf.map(|leaf| &leaf.leaf_type) {
+            None => Role::Drone,
+            Some(LeafType::Client) => Role::Client,
+            Some(LeafType::Server) => Role::Server,
+        };
+        topology.nodes.push((node.id, role));
+        topology.neighbours.insert(node.id, node.neighbours.clone());
+    }
+    topology
+        .nodes
+        .sort_unstable_by_key(|(id, role)| (*role, *id));
+    let changed = topology != state.topology;
+    if !state.dirty && (state.frozen || !changed) {
+        return;
+    }
+    state.dirty = false;
+    let Some(positions) = state.kind.positions(&topology) else {
+        return;
+    };
+    // Centred where the force simulation pulls the nodes to
+    let parameters = &force_graph.data.parameters;
+    let center = Vec2::new(parameters.center_x, parameters.center_y);
+    // Pinned nodes stay where the user left them
+    state.targets = nodes
+        .iter()
+        .filter(|(_, _, _, pinned)| !pinned)
+        .filter_map(|(entity, node, _, _)| Some((entity, center + *positions.get(&node.id)?)))
+        .collect();
+    state.topology = topology;
+}
+
+// Moves the nodes towards their targets, both on screen and in the force graph
+pub fn animate_layout(
+    mut state: ResMut<LayoutState>,
+    time: Res<Time>,
+    mut force_graph: ResMut<MyForceGraph>,
+    mut nodes: Query<(&mut Transform, &NodeForceGraphMarker), With<Node>>,
+) {
+    // Without the simulation the nodes only move while they are animated
+    if !state.simulating() {
+        let asleep = state.targets.is_empty();
+        if force_graph.asleep != asleep {
+            force_graph.asleep = asleep;
+        }
+    }
+    if state.targets.is_empty() {
+        return;
+    }
+    let step = 1.0 - (-ANIMATION_SPEED * time.delta_secs()).exp();
+    let mut arrived: HashMap<Entity, bool> = HashMap::new();
+    for (entity, target) in &state.targets {
+        let Ok((mut transform, petgraph)) = nodes.get_mut(*entity) else {
+            arrived.insert(*entity, true);
+            continue;
+        };
+        let current = transform.translation.truncate();
+        let done = current.distance(*target) < ARRIVED;
+        let position = if done {
+            *target
+        } else {
+            current.lerp(*target, step)
+        };
+        if force_graph.data.contains_node(petgraph.index) {
+            force_graph
+                .data
+                .set_node_position(petgraph.index, position.x, position.y);
+        }
+        transform.translation = position.extend(0.0);
+        arrived.insert(*entity, done);
+    }
+    state
+        .targets
+        .retain(|entity, _| !arrived.get(entity).copied().unwrap_or(true));
+}
@@ -26,6 +26,36 @@ mod command_sender;
 use command_sender::CommandsPlugin;
 mod event_listener;
 use event_listener::ListenerPlugin;
+mod metrics;
+use metrics::MetricsPlugin;
+mod export;
+use export::ExportPlugin;
+mod flood;
+use flood::FloodPlugin;
+mod pdr_check;
+use pdr_check::PdrCheckPlugin;
+mod protocol;
+use protocol::ProtocolPlugin;
+mod shortcut;
+use shortcut::ShortcutPlugin;
+mod heatmap;
+use heatmap::HeatmapPlugin;
+mod queues;
+use queues::QueuesPlugin;
+mod invariants;
+use invariants::InvariantsPlugin;
+mod timeline;
+use timeline::TimelinePlugin;
+mod search;
+use search::SearchPlugin;
+mod editing;
+use editing::EditingPlugin;
+mod context_menu;
+use context_menu::ContextMenuPlugin;
+mod layout;
+use layout::LayoutPlugin;
+mod fragility;
+use fragility::FragilityPlugin;
 
 use bevy::prelude::*;
 use bevy_egui::EguiPlugin;
@@ -43,5 +73,20 @@ pub fn loop_forever_sc(network: Network) {
         .add_plugins(PhysicsPlugin)
         .add_plugins(CommandsPlugin)
         .add_plugins(ListenerPlugin)
+        .add_plugins(MetricsPlugin)
+        .add_plugins(ExportPlugin)
+        .add_plugins(FloodPlugin)
+        .add_plugins(PdrCheckPlugin)
+        .add_plugins(ProtocolPlugin)
+        .add_plugins(ShortcutPlugin)
+        .add_plugins(HeatmapPlugin)
+        .add_plugins(QueuesPlugin)
+        .add_plugins(InvariantsPlugin)
+        .add_plugins(TimelinePlugin)
+        .add_plugins(SearchPlugin)
+        .add_plugins(EditingPlugin)
+        .add_plugins(ContextMenuPlugin)
+        .add_plugins(LayoutPlugin)
+        .add_plugins(FragilityPlugin)
         .run();
 }
@@ -0,0 +1,20 @@
+/// This module contains the metrics exporter plugin.
+/// When enabled from the settings window it serves the collected statistics on localhost
+/// in the Prometheus text format, so that long runs can be scraped by external tools.
+mod resources;
+mod server;
+mod systems;
+
+use bevy::prelude::*;
+pub use resources::MetricsResource;
+use systems::{toggle_metrics, update_metrics};
+
+pub struct MetricsPlugin;
+
+impl Plugin for MetricsPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(MetricsResource::default());
+        app.add_systems(Update, toggle_metrics);
+        app.add_systems(FixedUpdate, update_metrics);
+    }
+}
@@ -0,0 +1,14 @@
+use super::server::MetricsServer;
+use bevy::prelude::*;
+use std::sync::{Arc, Mutex};
+
+pub const METRICS_ADDRESS: &str = "127.0.0.1:9898";
+
+#[derive(Resource, Default)]
+pub struct MetricsResource {
+    pub enabled: bool,
+    // Last rendered exposition, shared with the server thread
+    pub body: Arc<Mutex<String>>,
+    // None while the server is stopped
+    pub server: Option<MetricsServer>,
+}
@@ -0,0 +1,65 @@
+use std::io::{ErrorKind, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+pub struct MetricsServer {
+    running: Arc<AtomicBool>,
+    thread: JoinHandle<()>,
+}
+
+impl MetricsServer {
+    /// Stops the server and waits for its thread, so that the address is free again.
+    pub fn stop(self) {
+        self.running.store(false, Ordering::Relaxed);
+        if self.thread.join().is_err() {
+            eprintln!("Metrics: server thread panicked");
+        }
+    }
+}
+
+pub fn start_server(address: &str, body: Arc<Mutex<String>>) -> Result<MetricsServer, String> {
+    let listener = TcpListener::bind(address).map_err(|err| err.to_string())?;
+    listener
+        .set_nonblocking(true)
+        .map_err(|err| err.to_string())?;
+    let running = Arc::new(AtomicBool::new(true));
+    let thread_running = running.clone();
+    let thread = thread::spawn(move || {
+        while thread_running.load(Ordering::Relaxed) {
+            match listener.accept() {
+                Ok((stream, _)) => {
+                    if let Err(err) = respond(stream, &body) {
+                        eprintln!("Metrics: error answering request: {err}");
+                    }
+                }
+                Err(err) if err.kind() == ErrorKind::WouldBlock => {
+                    thread::sleep(Duration::from_millis(50));
+                }
+                Err(err) => {
+                    eprintln!("Metrics: server stopped: {err}");
+                    return;
+                }
+            }
+        }
+    });
+    Ok(MetricsServer { running, thread })
+}
+
+fn respond(mut stream: TcpStream, body: &Arc<Mutex<String>>) -> std::io::Result<()> {
+    stream.set_nonblocking(false)?;
+    stream.set_read_timeout(Some(Duration::from_secs(1)))?;
+    // Every path is answered with the metrics, the request itself is not inspected
+    let mut request = [0; 1024];
+    let _ = stream.read(&mut request)?;
+    let body = body.lock().map(|body| body.clone()).unwrap_or_default();
+    write!(
+        stream,
+        "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        body.len(),
+        body
+    )?;
+    stream.flush()
+}
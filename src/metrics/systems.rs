@@ -0,0 +1,259 @@
+use super::resources::{MetricsResource, METRICS_ADDRESS};
+use super::server::start_server;
+use crate::components::{Drone, Edge, Leaf, Node};
+use crate::event_listener::resources::{DroneData, LeavesData, PacketTypeCounters};
+use crate::event_listener::DisplayedInfo;
+use crate::settings::MetricsEvent;
+use bevy::prelude::*;
+use std::collections::HashMap;
+use std::fmt::Write;
+use wg_2024::network::NodeId;
+
+const COUNTER: &str = "counter";
+const GAUGE: &str = "gauge";
+
+struct NodeLabels {
+    node_type: String,
+    name_impl: String,
+    queue: usize,
+}
+
+pub fn toggle_metrics(mut reader: EventReader<MetricsEvent>, mut metrics: ResMut<MetricsResource>) {
+    for _ in reader.read() {
+        if metrics.enabled {
+            if metrics.server.is_none() {
+                match start_server(METRICS_ADDRESS, metrics.body.clone()) {
+                    Ok(server) => {
+                        println!("Metrics served on http://{METRICS_ADDRESS}/metrics");
+                        metrics.server = Some(server);
+                    }
+                    Err(err) => {
+                        eprintln!("Can't start metrics server: {err}");
+                        metrics.enabled = false;
+                    }
+                }
+            }
+        } else if let Some(server) = metrics.server.take() {
+            server.stop();
+        }
+    }
+}
+
+pub fn update_metrics(
+    metrics: Res<MetricsResource>,
+    info: Res<DisplayedInfo>,
+    nodes: Query<(&Node, Option<&Drone>, Option<&Leaf>)>,
+    edges: Query<&Edge>,
+) {
+    if !metrics.enabled {
+        return;
+    }
+    let mut labels = HashMap::new();
+    for (node, drone, leaf) in nodes.iter() {
+        let node_type = match (drone, leaf) {
+            (Some(_), _) => "drone".to_string(),
+            (None, Some(leaf)) => leaf.leaf_type.to_string().to_lowercase(),
+            (None, None) => "unknown".to_string(),
+        };
+        labels.insert(
+            node.id,
+            NodeLabels {
+                node_type,
+                name_impl: node.name_impl.clone(),
+                queue: node.packet_channel.len(),
+            },
+        );
+    }
+    let body = render_metrics(&info, &labels, edges.iter().count());
+    if let Ok(mut shared) = metrics.body.lock() {
+        *shared = body;
+    }
+}
+
+fn render_metrics(
+    info: &DisplayedInfo,
+    labels: &HashMap<NodeId, NodeLabels>,
+    edge_count: usize,
+) -> String {
+    let node_labels = |id: NodeId, default_type: &str| match labels.get(&id) {
+        Some(l) => format!(
+            "node_id=\"{id}\",node_type=\"{}\",impl=\"{}\"",
+            l.node_type,
+            escape(&l.name_impl)
+        ),
+        None => format!("node_id=\"{id}\",node_type=\"{default_type}\",impl=\"unknown\""),
+    };
+    let drones = |value: fn(&DroneData) -> u64| {
+        info.drone
+            .iter()
+            .map(|(id, data)| (node_labels(*id, "drone"), value(data)))
+            .collect::<Vec<_>>()
+    };
+    let leaves = |value: fn(&LeavesData) -> u64| {
+        info.leaf
+            .iter()
+            .map(|(id, data)| (node_labels(*id, "leaf"), value(data)))
+            .collect::<Vec<_>>()
+    };
+
+    let mut out = String::new();
+    let mut sent = drones(|d| d.packets_sent);
+    sent.extend(leaves(|l| l.packets_sent));
+    family(
+        &mut out,
+        "sc_packets_sent_total",
+        "Packets sent",
+        COUNTER,
+        sent,
+    );
+    family(
+        &mut out,
+        "sc_packets_shortcutted_total",
+        "Packets shortcutted through the controller",
+        COUNTER,
+        drones(|d| d.packets_shortcutted),
+    );
+    let mut bytes = drones(|d| d.data_sent.0);
+    bytes.extend(leaves(|l| l.data_sent.0));
+    family(
+        &mut out,
+        "sc_bytes_sent_total",
+        "Fragment bytes sent",
+        COUNTER,
+        bytes,
+    );
+    family(
+        &mut out,
+        "sc_bytes_dropped_total",
+        "Fragment bytes dropped",
+        COUNTER,
+        drones(|d| d.data_dropped.0),
+    );
+    family(
+        &mut out,
+        "sc_fragments_dropped_total",
+        "Fragments dropped",
+        COUNTER,
+        drones(|d| d.fragments_dropped),
+    );
+    let per_neighbour = info
+        .drone
+        .iter()
+        .map(|(id, data)| (node_labels(*id, "drone"), &data.neighbours))
+        .chain(
+            info.leaf
+                .iter()
+                .map(|(id, data)| (node_labels(*id, "leaf"), &data.neighbours)),
+        )
+        .flat_map(|(labels, neighbours)| {
+            neighbours
+                .iter()
+                .map(move |(ngb, bytes)| (format!("{labels},neighbour=\"{ngb}\""), bytes.0))
+        });
+    family(
+        &mut out,
+        "sc_neighbour_bytes_sent_total",
+        "Fragment bytes sent to each neighbour",
+        COUNTER,
+        per_neighbour.collect(),
+    );
+    let by_type = info
+        .drone
+        .iter()
+        .map(|(id, data)| (node_labels(*id, "drone"), &data.packet_types))
+        .chain(
+            info.leaf
+                .iter()
+                .map(|(id, data)| (node_labels(*id, "leaf"), &data.packet_types)),
+        )
+        .flat_map(|(labels, counters)| {
+            type_samples(counters)
+                .into_iter()
+                .map(move |(kind, value)| (format!("{labels},{kind}"), value))
+        });
+    family(
+        &mut out,
+        "sc_packets_by_type_total",
+        "Packets sent split by packet and nack type",
+        COUNTER,
+        by_type.collect(),
+    );
+    family(
+        &mut out,
+        "sc_messages_completed_total",
+        "Messages fully sent",
+        COUNTER,
+        leaves(|l| l.msg_n),
+    );
+    let queues = labels
+        .iter()
+        .map(|(id, l)| (node_labels(*id, &l.node_type), l.queue as u64));
+    family(
+        &mut out,
+        "sc_queue_depth",
+        "Packets waiting in the node input channel",
+        GAUGE,
+        queues.collect(),
+    );
+    let nodes = vec![(String::new(), labels.len() as u64)];
+    family(
+        &mut out,
+        "sc_topology_nodes",
+        "Nodes in the topology",
+        GAUGE,
+        nodes,
+    );
+    let edges = vec![(String::new(), edge_count as u64)];
+    family(
+        &mut out,
+        "sc_topology_edges",
+        "Edges in the topology",
+        GAUGE,
+        edges,
+    );
+    out
+}
+
+fn type_samples(counters: &PacketTypeCounters) -> [(&'static str, u64); 8] {
+    [
+        ("packet_type=\"fragment\"", counters.fragments),
+        ("packet_type=\"ack\"", counters.acks),
+        (
+            "packet_type=\"nack\",nack_type=\"error_in_routing\"",
+            counters.nacks_error_in_routing,
+        ),
+        (
+            "packet_type=\"nack\",nack_type=\"destination_is_drone\"",
+            counters.nacks_destination_is_drone,
+        ),
+        (
+            "packet_type=\"nack\",nack_type=\"dropped\"",
+            counters.nacks_dropped,
+        ),
+        (
+            "packet_type=\"nack\",nack_type=\"unexpected_recipient\"",
+            counters.nacks_unexpected_recipient,
+        ),
+        ("packet_type=\"flood_request\"", counters.flood_requests),
+        ("packet_type=\"flood_response\"", counters.flood_responses),
+    ]
+}
+
+fn family(out: &mut String, name: &str, help: &str, kind: &str, samples: Vec<(String, u64)>) {
+    let _ = writeln!(out, "# HELP {name} {help}");
+    let _ = writeln!(out, "# TYPE {name} {kind}");
+    for (labels, value) in samples {
+        if labels.is_empty() {
+            let _ = writeln!(out, "{name} {value}");
+        } else {
+            let _ = writeln!(out, "{name}{{{labels}}} {value}");
+        }
+    }
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
@@ -0,0 +1,19 @@
+/// This module contains the PDR check plugin.
+/// It compares the drop ratio observed for every drone with the PDR it was configured with,
+/// and flags the drones whose behaviour is statistically incompatible with it.
+mod resources;
+mod systems;
+
+use bevy::prelude::*;
+use resources::PdrReport;
+use systems::{check_pdr, pdr_window};
+
+pub struct PdrCheckPlugin;
+
+impl Plugin for PdrCheckPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(PdrReport::default());
+        app.add_systems(FixedUpdate, check_pdr);
+        app.add_systems(Update, pdr_window);
+    }
+}
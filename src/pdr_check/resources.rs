@@ -0,0 +1,110 @@
+use bevy::prelude::*;
+use std::collections::{BTreeMap, HashMap};
+use wg_2024::network::NodeId;
+
+// Below this many fragments the normal approximation is not trusted
+const MIN_SAMPLES: u64 = 30;
+// Two-sided 95% quantile, used for the confidence interval
+const Z_INTERVAL: f64 = 1.96;
+// Two-sided 99.9% quantile, kept strict since every drone is tested every second
+const Z_CRITICAL: f64 = 3.29;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PdrVerdict {
+    NotEnoughData,
+    Consistent,
+    Suspicious,
+}
+
+// Counters observed when the drone got its current PDR
+#[derive(Debug, Clone)]
+pub struct PdrBaseline {
+    pub pdr: f32,
+    pub sent: u64,
+    pub dropped: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct PdrCheck {
+    pub pdr: f32,
+    pub sent: u64,
+    pub dropped: u64,
+    pub observed: f64,
+    pub interval: (f64, f64),
+    pub z_score: Option<f64>,
+    pub verdict: PdrVerdict,
+}
+
+impl PdrCheck {
+    /// Tests the fragments sent and dropped by a drone against its configured PDR.
+    pub fn evaluate(pdr: f32, sent: u64, dropped: u64) -> Self {
+        let n = sent + dropped;
+        let p0 = f64::from(pdr);
+        let observed = if n == 0 {
+            0.0
+        } else {
+            dropped as f64 / n as f64
+        };
+        let interval = wilson_interval(dropped, n);
+
+        let mut z_score = None;
+        let verdict = if p0 <= 0.0 {
+            // Any drop at PDR 0 is a violation, no statistics needed
+            if dropped > 0 {
+                PdrVerdict::Suspicious
+            } else if n < MIN_SAMPLES {
+                PdrVerdict::NotEnoughData
+            } else {
+                PdrVerdict::Consistent
+            }
+        } else if p0 >= 1.0 {
+            if sent > 0 {
+                PdrVerdict::Suspicious
+            } else if n < MIN_SAMPLES {
+                PdrVerdict::NotEnoughData
+            } else {
+                PdrVerdict::Consistent
+            }
+        } else if n < MIN_SAMPLES {
+            PdrVerdict::NotEnoughData
+        } else {
+            let n = n as f64;
+            let z = (dropped as f64 - n * p0) / (n * p0 * (1.0 - p0)).sqrt();
+            z_score = Some(z);
+            if z.abs() > Z_CRITICAL {
+                PdrVerdict::Suspicious
+            } else {
+                PdrVerdict::Consistent
+            }
+        };
+
+        PdrCheck {
+            pdr,
+            sent,
+            dropped,
+            observed,
+            interval,
+            z_score,
+            verdict,
+        }
+    }
+}
+
+fn wilson_interval(successes: u64, n: u64) -> (f64, f64) {
+    if n == 0 {
+        return (0.0, 1.0);
+    }
+    let n = n as f64;
+    let p = successes as f64 / n;
+    let z2 = Z_INTERVAL * Z_INTERVAL;
+    let denominator = 1.0 + z2 / n;
+    let centre = (p + z2 / (2.0 * n)) / denominator;
+    let margin = Z_INTERVAL * (p * (1.0 - p) / n + z2 / (4.0 * n * n)).sqrt() / denominator;
+    ((centre - margin).max(0.0), (centre + margin).min(1.0))
+}
+
+#[derive(Resource, Default)]
+pub struct PdrReport {
+    pub baselines: HashMap<NodeId, PdrBaseline>,
+    pub checks: BTreeMap<NodeId, PdrCheck>,
+}
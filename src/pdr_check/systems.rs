@@ -0,0 +1,84 @@
+use super::resources::{PdrBaseline, PdrCheck, PdrReport, PdrVerdict};
+use crate::components::{Drone, Node};
+use crate::event_listener::DisplayedInfo;
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+
+pub fn check_pdr(
+    drones: Query<(&Node, &Drone)>,
+    info: Res<DisplayedInfo>,
+    mut report: ResMut<PdrReport>,
+) {
+    let report = &mut *report;
+    report.checks.clear();
+    report
+        .baselines
+        .retain(|id, _| drones.iter().any(|(node, _)| node.id == *id));
+
+    for (node, drone) in drones.iter() {
+        let (sent, dropped) = info
+            .drone
+            .get(&node.id)
+            .map_or((0, 0), |d| (d.packet_types.fragments, d.fragments_dropped));
+        let baseline = report
+            .baselines
+            .entry(node.id)
+            .or_insert_with(|| PdrBaseline {
+                pdr: drone.pdr,
+                sent,
+                dropped,
+            });
+        // Restart the sample when the PDR changes or the infos are reset
+        if (baseline.pdr - drone.pdr).abs() > f32::EPSILON
+            || sent < baseline.sent
+            || dropped < baseline.dropped
+        {
+            *baseline = PdrBaseline {
+                pdr: drone.pdr,
+                sent,
+                dropped,
+            };
+        }
+        report.checks.insert(
+            node.id,
+            PdrCheck::evaluate(drone.pdr, sent - baseline.sent, dropped - baseline.dropped),
+        );
+    }
+}
+
+pub fn pdr_window(mut contexts: EguiContexts, report: Res<PdrReport>) {
+    egui::Window::new("PDR check")
+        .default_open(false)
+        .show(contexts.ctx_mut(), |ui| {
+            ui.label("Drop ratio observed since the last PDR change, with its 95% interval");
+            ui.separator();
+            egui::Grid::new("pdr_check").striped(true).show(ui, |ui| {
+                ui.label("Drone");
+                ui.label("PDR");
+                ui.label("Fragments");
+                ui.label("Observed");
+                ui.label("95% interval");
+                ui.label("z");
+                ui.label("Verdict");
+                ui.end_row();
+                for (id, check) in &report.checks {
+                    ui.label(id.to_string());
+                    ui.label(format!("{:.2}", check.pdr));
+                    ui.label((check.sent + check.dropped).to_string());
+                    ui.label(format!("{:.3}", check.observed));
+                    ui.label(format!("{:.3} - {:.3}", check.interval.0, check.interval.1));
+                    ui.label(check.z_score.map_or("-".to_string(), |z| format!("{z:.2}")));
+                    match check.verdict {
+                        PdrVerdict::NotEnoughData => ui.label("not enough data"),
+                        PdrVerdict::Consistent => {
+                            ui.colored_label(egui::Color32::LIGHT_GREEN, "consistent")
+                        }
+                        PdrVerdict::Suspicious => {
+                            ui.colored_label(egui::Color32::LIGHT_RED, "IGNORES PDR")
+                        }
+                    };
+                    ui.end_row();
+                }
+            });
+        });
+}
@@ -1,12 +1,43 @@
+use crate::components::Node;
+use crate::resources::NodeIndex;
+use bevy::ecs::{component::ComponentId, world::DeferredWorld};
 use bevy::prelude::*;
 
 #[derive(Component)]
+#[component(on_add = index_graph_node, on_remove = unindex_graph_node)]
 pub struct NodeForceGraphMarker {
     pub index: petgraph::stable_graph::NodeIndex<u8>,
 }
 
+fn index_graph_node(mut world: DeferredWorld, entity: Entity, _: ComponentId) {
+    set_graph_index(&mut world, entity, true);
+}
+
+fn unindex_graph_node(mut world: DeferredWorld, entity: Entity, _: ComponentId) {
+    set_graph_index(&mut world, entity, false);
+}
+
+fn set_graph_index(world: &mut DeferredWorld, entity: Entity, added: bool) {
+    let Some(id) = world.get::<Node>(entity).map(|node| node.id) else {
+        return;
+    };
+    let index = world
+        .get::<NodeForceGraphMarker>(entity)
+        .map(|marker| marker.index)
+        .filter(|_| added);
+    if let Some(mut node_index) = world.get_resource_mut::<NodeIndex>() {
+        node_index.set_graph_index(id, index);
+    }
+}
+
 #[derive(Component)]
 pub struct EdgeForceGraphMarker {
     pub start_node: petgraph::stable_graph::NodeIndex<u8>,
     pub end_node: petgraph::stable_graph::NodeIndex<u8>,
 }
+
+// A node pinned by the user, it keeps this position until it is unpinned
+#[derive(Component)]
+pub struct Pinned {
+    pub position: Vec2,
+}
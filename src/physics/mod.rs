@@ -1,14 +1,23 @@
 /// This module contains all the systems that are used to update the physics
 /// This module relys on the force graph implementation, check that folder for more infos.
+/// Nodes can be dragged around, and pinned in place with a double click.
+/// The simulation only runs with the force layout, the other layouts are in the layout module.
+/// Once the layout has settled the simulation sleeps, and the window is redrawn less often.
 ///
 mod components;
 mod resources;
 mod systems;
 
+use crate::event_listener::ingestion_running;
+use crate::layout::simulation_running;
 use bevy::prelude::*;
-use resources::MyForceGraph;
+pub use components::{NodeForceGraphMarker, Pinned};
+use resources::LastClick;
+pub use resources::{LayoutPreset, MyForceGraph};
 use systems::{
-    remove_items, update_edges, update_graph, update_nodes, update_selector, update_text,
+    drag_node, draw_pins, drop_node, follow_view, power_saving, remove_graph_edge,
+    remove_graph_node, reset_layout, settled_indicator, toggle_pin, update_edges, update_graph,
+    update_nodes, update_selector, update_text,
 };
 
 pub struct PhysicsPlugin;
@@ -16,11 +25,27 @@ pub struct PhysicsPlugin;
 impl Plugin for PhysicsPlugin {
     fn build(&self, app: &mut App) {
         app.insert_resource(MyForceGraph::new());
+        app.insert_resource(LastClick::default());
         app.add_systems(Update, update_graph);
-        app.add_systems(FixedUpdate, remove_items);
-        app.add_systems(Update, update_nodes);
+        app.add_systems(
+            Update,
+            (
+                follow_view,
+                update_nodes
+                    .run_if(ingestion_running)
+                    .run_if(simulation_running),
+            )
+                .chain(),
+        );
         app.add_systems(Update, update_edges);
         app.add_systems(Update, update_text);
         app.add_systems(Update, update_selector);
+        app.add_systems(Update, (reset_layout, draw_pins));
+        app.add_systems(Update, (power_saving, settled_indicator));
+        app.add_observer(drag_node);
+        app.add_observer(drop_node);
+        app.add_observer(toggle_pin);
+        app.add_observer(remove_graph_node);
+        app.add_observer(remove_graph_edge);
     }
 }
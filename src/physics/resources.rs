@@ -1,21 +1,103 @@
 use bevy::prelude::*;
 use force_graph::{ForceGraph, NodeData, SimulationParameters};
 
+// Mean kinetic energy per node under which the layout is considered still, for a node of the
+// default mass it is a speed of about 3 units per second
+pub const SETTLED_ENERGY: f32 = 50.0;
+// How long the layout has to stay still before the simulation goes to sleep
+pub const SETTLED_SECS: f32 = 1.0;
+
 #[derive(Resource)]
 pub struct MyForceGraph {
     pub data: ForceGraph<NodeData>,
+    // The nodes are pulled towards the middle of the visible area
+    pub follow_view: bool,
+    // The simulation isn't stepped while the layout is settled, until something changes
+    pub asleep: bool,
+    // Seconds the layout has been still for
+    pub still_secs: f32,
+    // Parameters at the last step, the graph changes wake the simulation where they happen
+    last_parameters: SimulationParameters,
 }
 
 impl MyForceGraph {
     pub fn new() -> Self {
+        let mut parameters = SimulationParameters::default();
+        LayoutPreset::Standard.apply(&mut parameters);
         Self {
-            data: ForceGraph::new(SimulationParameters {
-                force_charge: 4000.0,
-                force_spring: 0.1,
-                force_max: 140.0,
-                node_speed: 4000.0,
-                damping_factor: 0.98,
-            }),
+            last_parameters: parameters.clone(),
+            data: ForceGraph::new(parameters),
+            follow_view: false,
+            asleep: false,
+            still_secs: 0.0,
+        }
+    }
+
+    /// Restarts the simulation after something moved the nodes.
+    pub fn wake(&mut self) {
+        self.asleep = false;
+        self.still_secs = 0.0;
+    }
+
+    /// Whether the parameters changed since the last call.
+    pub fn parameters_changed(&mut self) -> bool {
+        if self.data.parameters == self.last_parameters {
+            return false;
+        }
+        self.last_parameters = self.data.parameters.clone();
+        true
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LayoutPreset {
+    Standard,
+    Compact,
+    Spread,
+    Presentation,
+}
+
+impl LayoutPreset {
+    pub const ALL: [LayoutPreset; 4] = [
+        LayoutPreset::Standard,
+        LayoutPreset::Compact,
+        LayoutPreset::Spread,
+        LayoutPreset::Presentation,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            LayoutPreset::Standard => "Standard",
+            LayoutPreset::Compact => "Compact",
+            LayoutPreset::Spread => "Spread",
+            LayoutPreset::Presentation => "Presentation",
         }
     }
+
+    /// Sets the forces of the simulation, the centre is left where it is.
+    pub fn apply(self, parameters: &mut SimulationParameters) {
+        let (force_charge, force_spring, force_max, node_speed, damping_factor) = match self {
+            LayoutPreset::Standard => (4000.0, 0.1, 140.0, 4000.0, 0.98),
+            // Small topologies, or large ones that have to fit on screen
+            LayoutPreset::Compact => (1500.0, 0.2, 140.0, 4000.0, 0.95),
+            // Dense topologies, where the edges would otherwise overlap
+            LayoutPreset::Spread => (10000.0, 0.05, 280.0, 4000.0, 0.98),
+            // Slow and calm movements, for a projector
+            LayoutPreset::Presentation => (6000.0, 0.08, 100.0, 2000.0, 0.9),
+        };
+        parameters.force_charge = force_charge;
+        parameters.force_spring = force_spring;
+        parameters.force_max = force_max;
+        parameters.node_speed = node_speed;
+        parameters.damping_factor = damping_factor;
+    }
+}
+
+// Two clicks on the same node closer than this, in seconds, are a double click
+pub const DOUBLE_CLICK_SECS: f32 = 0.4;
+
+#[derive(Resource, Default)]
+pub struct LastClick {
+    pub entity: Option<Entity>,
+    pub time: f32,
 }
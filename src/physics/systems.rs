@@ -1,17 +1,27 @@
 use super::{
-    components::{EdgeForceGraphMarker, NodeForceGraphMarker},
-    resources::MyForceGraph,
+    components::{EdgeForceGraphMarker, NodeForceGraphMarker, Pinned},
+    resources::{LastClick, MyForceGraph, DOUBLE_CLICK_SECS, SETTLED_ENERGY, SETTLED_SECS},
 };
 use crate::components::{Edge, Node, SelectedMarker, SelectionSpriteMarker, Text};
+use crate::editing::EditMode;
+use crate::resources::NodeIndex;
+use crate::settings::ResetLayoutEvent;
+use crate::timeline::Timeline;
 use bevy::prelude::*;
+use bevy::winit::{UpdateMode, WinitSettings};
+use bevy_egui::{egui, EguiContexts};
 use force_graph::{EdgeData, NodeData};
+use rand::Rng;
+use std::time::Duration;
+
+const PIN_COLOR: Color = Color::srgb(1.0, 0.85, 0.0);
 
 pub fn update_graph(
     mut commands: Commands,
     mut force_graph: ResMut<MyForceGraph>,
     nodes: Query<(Entity, &Transform), (With<Node>, Without<NodeForceGraphMarker>)>,
     edges: Query<(Entity, &Edge), Without<EdgeForceGraphMarker>>,
-    nodes_in_graph: Query<(&Node, &NodeForceGraphMarker)>,
+    index: Res<NodeIndex>,
 ) {
     for (entity, transform) in nodes.iter() {
         let petgraph_index = force_graph.data.add_node(NodeData {
@@ -22,21 +32,14 @@ pub fn update_graph(
         commands.entity(entity).insert(NodeForceGraphMarker {
             index: petgraph_index,
         });
+        force_graph.wake();
     }
     for (entity, edge) in edges.iter() {
-        let mut start_node_index = None;
-        let mut end_node_index = None;
-
-        for (node, petgraph) in nodes_in_graph.iter() {
-            if edge.start_node == node.id {
-                start_node_index = Some(petgraph.index);
-            }
-            if edge.end_node == node.id {
-                end_node_index = Some(petgraph.index);
-            }
-        }
-
-        if let (Some(start_node), Some(end_node)) = (start_node_index, end_node_index) {
+        // Nodes added in this frame are in the index from the next one
+        if let (Some(start_node), Some(end_node)) = (
+            index.graph_index(edge.start_node),
+            index.graph_index(edge.end_node),
+        ) {
             if start_node != end_node
                 && force_graph.data.contains_node(start_node)
                 && force_graph.data.contains_node(end_node)
@@ -44,6 +47,7 @@ pub fn update_graph(
                 force_graph
                     .data
                     .add_edge(start_node, end_node, EdgeData::default());
+                force_graph.wake();
             }
             commands.entity(entity).insert(EdgeForceGraphMarker {
                 start_node,
@@ -53,38 +57,37 @@ pub fn update_graph(
     }
 }
 
-pub fn remove_items(
+// A despawned node leaves the force graph at once, together with its edges
+pub fn remove_graph_node(
+    trigger: Trigger<OnRemove, NodeForceGraphMarker>,
     mut force_graph: ResMut<MyForceGraph>,
     nodes: Query<&NodeForceGraphMarker>,
-    edges: Query<&EdgeForceGraphMarker>,
 ) {
-    let node_count = force_graph.data.get_graph().node_count() - 1;
-    if node_count > nodes.iter().count() {
-        let indices: Vec<_> = force_graph
-            .data
-            .get_nodes_indices()
-            .iter()
-            .copied()
-            .collect();
-        for idx in &indices {
-            if !nodes.iter().any(|node| node.index == *idx) {
-                force_graph.data.remove_node(*idx);
-            }
-        }
+    if let Ok(petgraph) = nodes.get(trigger.entity()) {
+        force_graph.data.remove_node(petgraph.index);
+        force_graph.wake();
     }
+}
 
-    let edge_count =
-        force_graph.data.get_graph().edge_count() - force_graph.data.get_graph().node_count();
-    if edge_count > edges.iter().count() {
-        let indices: Vec<_> = force_graph.data.get_edges_indices().clone();
-        for (start, end) in &indices {
-            if !edges
-                .iter()
-                .any(|edge| edge.start_node == *start && edge.end_node == *end)
-            {
-                force_graph.data.remove_edge(*start, *end);
-            }
-        }
+pub fn remove_graph_edge(
+    trigger: Trigger<OnRemove, EdgeForceGraphMarker>,
+    mut force_graph: ResMut<MyForceGraph>,
+    edges: Query<&EdgeForceGraphMarker>,
+) {
+    let Ok(petgraph) = edges.get(trigger.entity()) else {
+        return;
+    };
+    // Already gone when the edge goes away with one of its nodes
+    if force_graph
+        .data
+        .get_graph()
+        .find_edge(petgraph.start_node, petgraph.end_node)
+        .is_some()
+    {
+        force_graph
+            .data
+            .remove_edge(petgraph.start_node, petgraph.end_node);
+        force_graph.wake();
     }
 }
 
@@ -105,6 +108,12 @@ pub fn update_nodes(
     time: Res<Time>,
     mut nodes: Query<(&mut Transform, &NodeForceGraphMarker), With<Node>>,
 ) {
+    if force_graph.parameters_changed() {
+        force_graph.wake();
+    }
+    if force_graph.asleep {
+        return;
+    }
     let mut delta_sec = time.delta_secs();
     delta_sec = delta_sec.clamp(0.0, 0.1);
     accurate_update(&mut force_graph, delta_sec);
@@ -114,24 +123,78 @@ pub fn update_nodes(
             transform.translation = Vec3::new(x, y, 0.0);
         }
     }
+
+    #[allow(clippy::cast_precision_loss)]
+    let nodes = force_graph.data.get_nodes_indices().len().max(1) as f32;
+    if force_graph.data.kinetic_energy() / nodes < SETTLED_ENERGY {
+        force_graph.still_secs += delta_sec;
+        force_graph.asleep = force_graph.still_secs >= SETTLED_SECS;
+    } else {
+        force_graph.still_secs = 0.0;
+    }
+}
+
+// While nothing moves the window is only redrawn on input, or a few times per second to keep
+// showing the events
+pub fn power_saving(force_graph: Res<MyForceGraph>, mut winit: ResMut<WinitSettings>) {
+    let settings = if force_graph.asleep {
+        WinitSettings {
+            focused_mode: UpdateMode::reactive(Duration::from_millis(50)),
+            unfocused_mode: UpdateMode::reactive_low_power(Duration::from_millis(200)),
+        }
+    } else {
+        WinitSettings {
+            focused_mode: UpdateMode::Continuous,
+            unfocused_mode: UpdateMode::Continuous,
+        }
+    };
+    if winit.focused_mode != settings.focused_mode {
+        *winit = settings;
+    }
+}
+
+pub fn settled_indicator(mut contexts: EguiContexts, force_graph: Res<MyForceGraph>) {
+    let (text, color) = if force_graph.asleep {
+        ("Layout settled", egui::Color32::LIGHT_GREEN)
+    } else {
+        ("Layout moving", egui::Color32::YELLOW)
+    };
+    egui::Area::new(egui::Id::new("settled_indicator"))
+        .anchor(egui::Align2::RIGHT_TOP, egui::vec2(-10.0, 10.0))
+        .show(contexts.ctx_mut(), |ui| {
+            ui.colored_label(color, text);
+        });
+}
+
+// Keeps the centre of the simulation in the middle of the visible area
+pub fn follow_view(
+    mut force_graph: ResMut<MyForceGraph>,
+    camera: Single<&Transform, With<Camera>>,
+) {
+    let parameters = &force_graph.data.parameters;
+    let (x, y) = (camera.translation.x, camera.translation.y);
+    if !force_graph.follow_view || (parameters.center_x == x && parameters.center_y == y) {
+        return;
+    }
+    force_graph.data.parameters.center_x = x;
+    force_graph.data.parameters.center_y = y;
 }
 
 pub fn update_edges(
     mut edge_query: Query<(&Edge, &mut Transform)>,
-    node_query: Query<(&Node, &Transform), Without<Edge>>,
+    node_query: Query<&Transform, (With<Node>, Without<Edge>)>,
+    index: Res<NodeIndex>,
 ) {
+    let position = |id| {
+        let entity = index.entity(id)?;
+        node_query
+            .get(entity)
+            .ok()
+            .map(|transform| transform.translation)
+    };
     for (edge, mut edge_transform) in &mut edge_query {
-        let start_node_transform = node_query
-            .iter()
-            .find(|(node, _)| node.id == edge.start_node)
-            .map(|(_, transform)| transform.translation);
-        let end_node_transform = node_query
-            .iter()
-            .find(|(node, _)| node.id == edge.end_node)
-            .map(|(_, transform)| transform.translation);
-
         if let (Some(start_position), Some(end_position)) =
-            (start_node_transform, end_node_transform)
+            (position(edge.start_node), position(edge.end_node))
         {
             let midpoint = (start_position + end_position) / 2.0;
             let direction = end_position - start_position;
@@ -140,24 +203,23 @@ pub fn update_edges(
 
             edge_transform.translation = midpoint;
             edge_transform.rotation = Quat::from_rotation_z(angle);
-            edge_transform.scale = Vec3::new(distance, 1.0, 1.0);
+            // The thickness is left to the heatmap
+            edge_transform.scale = Vec3::new(distance, edge_transform.scale.y, 1.0);
         }
     }
 }
 
 pub fn update_text(
     mut query_text: Query<(&Text, &mut Transform)>,
-    query_node: Query<(Entity, &Transform), (With<Node>, Without<Text>)>,
+    query_node: Query<&Transform, (With<Node>, Without<Text>)>,
 ) {
     for (text, mut transform) in &mut query_text {
-        for (entity, node_transform) in &query_node {
-            if entity == text.entity_id {
-                transform.translation = Vec3::new(
-                    node_transform.translation.x,
-                    node_transform.translation.y + 15.0,
-                    15.0,
-                );
-            }
+        if let Ok(node_transform) = query_node.get(text.entity_id) {
+            transform.translation = Vec3::new(
+                node_transform.translation.x,
+                node_transform.translation.y + 15.0,
+                15.0,
+            );
         }
     }
 }
@@ -166,14 +228,293 @@ pub fn update_selector(
     node_query: Query<&Transform, (With<SelectedMarker>, Without<SelectionSpriteMarker>)>,
     mut selector_query: Query<(&mut Transform, &mut Visibility), With<SelectionSpriteMarker>>,
 ) {
-    if node_query.iter().count() == 0 {
+    // With many nodes selected each one gets a ring instead
+    let Ok(node_transform) = node_query.get_single() else {
         for (_transform, mut visibility) in &mut selector_query {
             *visibility = Visibility::Hidden;
         }
+        return;
+    };
+    for (mut transform, mut visibility) in &mut selector_query {
+        transform.translation = node_transform.translation;
+        *visibility = Visibility::Visible;
     }
-    for node_transform in node_query.iter() {
-        for (mut transform, _visibility) in &mut selector_query {
-            transform.translation = node_transform.translation;
+}
+
+// The dragged node is anchored while it follows the pointer, so the other forces don't move it
+pub fn drag_node(
+    trigger: Trigger<Pointer<Drag>>,
+    camera: Single<(&Camera, &GlobalTransform)>,
+    mut force_graph: ResMut<MyForceGraph>,
+    mut nodes: Query<(&mut Transform, &NodeForceGraphMarker, Option<&mut Pinned>), With<Node>>,
+    edit_mode: Res<EditMode>,
+    timeline: Res<Timeline>,
+) {
+    // In editing mode dragging a node draws a new edge instead, and the history view is
+    // read-only: observers have no run condition, so it is checked here
+    if trigger.event().button != PointerButton::Primary
+        || edit_mode.enabled
+        || timeline.viewing.is_some()
+    {
+        return;
+    }
+    let Ok((mut transform, petgraph, pinned)) = nodes.get_mut(trigger.entity()) else {
+        return;
+    };
+    let (camera, camera_transform) = *camera;
+    let Ok(position) =
+        camera.viewport_to_world_2d(camera_transform, trigger.event().pointer_location.position)
+    else {
+        return;
+    };
+    if force_graph.data.contains_node(petgraph.index) {
+        force_graph
+            .data
+            .set_node_position(petgraph.index, position.x, position.y);
+        force_graph.data.set_anchor(petgraph.index, true);
+        force_graph.wake();
+    }
+    transform.translation = position.extend(0.0);
+    if let Some(mut pinned) = pinned {
+        pinned.position = position;
+    }
+}
+
+pub fn drop_node(
+    trigger: Trigger<Pointer<DragEnd>>,
+    mut force_graph: ResMut<MyForceGraph>,
+    nodes: Query<(&NodeForceGraphMarker, Has<Pinned>), With<Node>>,
+    timeline: Res<Timeline>,
+) {
+    if timeline.viewing.is_some() {
+        return;
+    }
+    let Ok((petgraph, pinned)) = nodes.get(trigger.entity()) else {
+        return;
+    };
+    if force_graph.data.contains_node(petgraph.index) {
+        force_graph.data.set_anchor(petgraph.index, pinned);
+        force_graph.wake();
+    }
+}
+
+pub fn toggle_pin(
+    trigger: Trigger<Pointer<Click>>,
+    mut commands: Commands,
+    time: Res<Time>,
+    mut last_click: ResMut<LastClick>,
+    mut force_graph: ResMut<MyForceGraph>,
+    nodes: Query<(&Transform, &NodeForceGraphMarker, Has<Pinned>), With<Node>>,
+    timeline: Res<Timeline>,
+) {
+    if timeline.viewing.is_some() {
+        return;
+    }
+    let entity = trigger.entity();
+    let Ok((transform, petgraph, pinned)) = nodes.get(entity) else {
+        return;
+    };
+    let now = time.elapsed_secs();
+    if last_click.entity != Some(entity) || now - last_click.time > DOUBLE_CLICK_SECS {
+        *last_click = LastClick {
+            entity: Some(entity),
+            time: now,
+        };
+        return;
+    }
+    last_click.entity = None;
+    if pinned {
+        commands.entity(entity).remove::<Pinned>();
+    } else {
+        commands.entity(entity).insert(Pinned {
+            position: transform.translation.truncate(),
+        });
+    }
+    if force_graph.data.contains_node(petgraph.index) {
+        force_graph.data.set_anchor(petgraph.index, !pinned);
+        force_graph.wake();
+    }
+}
+
+pub fn draw_pins(nodes: Query<&Transform, (With<Pinned>, With<Node>)>, mut gizmos: Gizmos) {
+    for transform in nodes.iter() {
+        let position = transform.translation.truncate() + Vec2::new(18.0, 18.0);
+        gizmos.circle_2d(position, 5.0, PIN_COLOR);
+    }
+}
+
+// Scatters the nodes like at startup, the pinned ones go back to their pinned position
+pub fn reset_layout(
+    mut reader: EventReader<ResetLayoutEvent>,
+    mut force_graph: ResMut<MyForceGraph>,
+    mut nodes: Query<(&mut Transform, &NodeForceGraphMarker, Option<&Pinned>), With<Node>>,
+) {
+    if reader.read().count() == 0 {
+        return;
+    }
+    let mut rng = rand::rng();
+    for (mut transform, petgraph, pinned) in &mut nodes {
+        let position = pinned.map_or_else(
+            || {
+                Vec2::new(
+                    rng.random_range(-200.0..100.0),
+                    rng.random_range(-150.0..150.0),
+                )
+            },
+            |pinned| pinned.position,
+        );
+        if force_graph.data.contains_node(petgraph.index) {
+            force_graph
+                .data
+                .set_node_position(petgraph.index, position.x, position.y);
         }
+        transform.translation = position.extend(0.0);
+    }
+    force_graph.wake();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossbeam_channel::unbounded;
+    use std::collections::{BTreeSet, HashSet};
+    use wg_2024::network::NodeId;
+
+    fn app() -> App {
+        let mut app = App::new();
+        app.insert_resource(MyForceGraph::new());
+        app.insert_resource(NodeIndex::default());
+        app.add_systems(Update, update_graph);
+        app.add_observer(remove_graph_node);
+        app.add_observer(remove_graph_edge);
+        app
+    }
+
+    fn spawn_node(app: &mut App, id: NodeId) -> Entity {
+        let node = Node {
+            id,
+            entity_id: Entity::PLACEHOLDER,
+            neighbours: HashSet::new(),
+            packet_channel: unbounded().0,
+            name_impl: String::new(),
+        };
+        app.world_mut().spawn((node, Transform::default())).id()
+    }
+
+    fn spawn_edge(app: &mut App, start_node: NodeId, end_node: NodeId) -> Entity {
+        app.world_mut()
+            .spawn(Edge {
+                start_node,
+                end_node,
+            })
+            .id()
+    }
+
+    // Every node and edge entity has its counterpart in the force graph, and nothing else is there
+    fn assert_in_sync(app: &mut App) {
+        let world = app.world_mut();
+        let nodes: BTreeSet<_> = world
+            .query::<&NodeForceGraphMarker>()
+            .iter(world)
+            .map(|petgraph| petgraph.index)
+            .collect();
+        let edges: BTreeSet<_> = world
+            .query::<&EdgeForceGraphMarker>()
+            .iter(world)
+            .map(|petgraph| {
+                let (a, b) = (petgraph.start_node, petgraph.end_node);
+                (a.min(b), a.max(b))
+            })
+            .collect();
+        let force_graph = &world.resource::<MyForceGraph>().data;
+        let graph_edges: BTreeSet<_> = force_graph
+            .get_edges_indices()
+            .into_iter()
+            .map(|(a, b)| (a.min(b), a.max(b)))
+            .collect();
+        assert_eq!(&nodes, force_graph.get_nodes_indices());
+        assert_eq!(edges, graph_edges);
+    }
+
+    // Nodes join the graph in the first update, their edges in the following one
+    fn triangle(app: &mut App) -> ([Entity; 3], [Entity; 3]) {
+        let nodes = [1, 2, 3].map(|id| spawn_node(app, id));
+        let edges = [(1, 2), (2, 3), (3, 1)].map(|(a, b)| spawn_edge(app, a, b));
+        app.update();
+        app.update();
+        (nodes, edges)
+    }
+
+    #[test]
+    fn spawned_nodes_and_edges_join_the_graph() {
+        let mut app = app();
+        triangle(&mut app);
+        assert_in_sync(&mut app);
+        let force_graph = &app.world().resource::<MyForceGraph>().data;
+        assert_eq!(force_graph.get_nodes_indices().len(), 3);
+        assert_eq!(force_graph.get_edges_indices().len(), 3);
+    }
+
+    #[test]
+    fn despawned_edge_leaves_the_graph_at_once() {
+        let mut app = app();
+        let (_, edges) = triangle(&mut app);
+        app.world_mut().despawn(edges[0]);
+        assert_in_sync(&mut app);
+        let force_graph = &app.world().resource::<MyForceGraph>().data;
+        assert_eq!(force_graph.get_nodes_indices().len(), 3);
+        assert_eq!(force_graph.get_edges_indices().len(), 2);
+    }
+
+    #[test]
+    fn crashed_node_leaves_the_graph_with_its_edges() {
+        let mut app = app();
+        let (nodes, edges) = triangle(&mut app);
+        // Like a crash, the node goes first and then its edges
+        app.world_mut().despawn(nodes[0]);
+        assert_eq!(
+            app.world()
+                .resource::<MyForceGraph>()
+                .data
+                .get_edges_indices()
+                .len(),
+            1
+        );
+        app.world_mut().despawn(edges[0]);
+        app.world_mut().despawn(edges[2]);
+        assert_in_sync(&mut app);
+        app.update();
+        assert_in_sync(&mut app);
+    }
+
+    #[test]
+    fn graph_follows_nodes_added_after_removals() {
+        let mut app = app();
+        let (nodes, edges) = triangle(&mut app);
+        app.world_mut().despawn(edges[1]);
+        app.world_mut().despawn(edges[2]);
+        app.world_mut().despawn(nodes[2]);
+        // The new drone reuses both the id and the force graph index of the crashed one
+        spawn_node(&mut app, 3);
+        spawn_edge(&mut app, 3, 1);
+        spawn_edge(&mut app, 3, 2);
+        app.update();
+        app.update();
+        assert_in_sync(&mut app);
+        let force_graph = &app.world().resource::<MyForceGraph>().data;
+        assert_eq!(force_graph.get_nodes_indices().len(), 3);
+        assert_eq!(force_graph.get_edges_indices().len(), 3);
+    }
+
+    #[test]
+    fn graph_changes_wake_the_simulation_even_when_counts_match() {
+        let mut app = app();
+        let (_, edges) = triangle(&mut app);
+        app.world_mut().resource_mut::<MyForceGraph>().asleep = true;
+        // A disconnect and a connect in the same frame leave as many edges as before
+        app.world_mut().despawn(edges[0]);
+        spawn_edge(&mut app, 1, 2);
+        app.update();
+        assert!(!app.world().resource::<MyForceGraph>().asleep);
     }
 }
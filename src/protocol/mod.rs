@@ -0,0 +1,19 @@
+/// This module contains the protocol monitor plugin.
+/// The monitor checks every packet event received by the controller against the WGL protocol
+/// and the known topology, and lists the violations it finds in the alerts window.
+pub mod resources;
+mod systems;
+
+use bevy::prelude::*;
+pub use resources::ProtocolMonitor;
+use systems::{alerts_window, track_crashes};
+
+pub struct ProtocolPlugin;
+
+impl Plugin for ProtocolPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(ProtocolMonitor::default());
+        app.add_systems(Update, track_crashes);
+        app.add_systems(Update, alerts_window);
+    }
+}
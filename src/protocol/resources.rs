@@ -0,0 +1,214 @@
+use bevy::prelude::*;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt::Display;
+use wg_2024::network::NodeId;
+use wg_2024::packet::{Packet, PacketType};
+
+// Older alerts are forgotten once this many are stored
+const MAX_ALERTS: usize = 200;
+// The hop index history is cleared when it grows past this many packets
+const MAX_TRACKED_PACKETS: usize = 10_000;
+// Fragments a drone forwarded before handling its crash command can still be in the event
+// channel, its packets are only flagged once this many seconds have passed since the crash
+pub const CRASH_GRACE_SECS: f32 = 2.0;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ViolationKind {
+    NotANeighbour,
+    HopIndexNotAdvanced,
+    ForwardAfterCrash,
+    SenderNotOnHeader,
+    DroppedNotFragment,
+}
+
+impl Display for ViolationKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ViolationKind::NotANeighbour => write!(f, "Sent to a non neighbour"),
+            ViolationKind::HopIndexNotAdvanced => write!(f, "Hop index not advanced"),
+            ViolationKind::ForwardAfterCrash => write!(f, "Forwarded after crash"),
+            ViolationKind::SenderNotOnHeader => write!(f, "Sender not on routing header"),
+            ViolationKind::DroppedNotFragment => write!(f, "Dropped a non fragment"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ProtocolAlert {
+    pub time: f32,
+    pub kind: ViolationKind,
+    pub nodes: Vec<NodeId>,
+    pub description: String,
+    pub packet: String,
+}
+
+// What the monitor needs to know about a node of the topology
+pub struct NodeView<'a> {
+    pub neighbours: &'a HashSet<NodeId>,
+    pub is_leaf: bool,
+}
+
+#[derive(Resource, Default)]
+pub struct ProtocolMonitor {
+    pub alerts: VecDeque<ProtocolAlert>,
+    // Every node seen in the topology, with whether it is a leaf
+    pub known: HashMap<NodeId, bool>,
+    // Crashed drones with the time they left the topology
+    pub crashed: HashMap<NodeId, f32>,
+    // Hop index each packet carried when it was last sent to a node. Kept per node, the
+    // events of the drones and of the leaves can be seen in any order within a frame
+    received_hop_index: HashMap<((u64, u8, u64), NodeId), usize>,
+}
+
+impl ProtocolMonitor {
+    fn alert(
+        &mut self,
+        time: f32,
+        kind: ViolationKind,
+        nodes: Vec<NodeId>,
+        description: String,
+        packet: &Packet,
+    ) {
+        if self.alerts.len() >= MAX_ALERTS {
+            self.alerts.pop_front();
+        }
+        self.alerts.push_back(ProtocolAlert {
+            time,
+            kind,
+            nodes,
+            description,
+            packet: packet.to_string(),
+        });
+    }
+
+    /// Checks a packet sent by a drone (`from_drone`) or by a leaf.
+    pub fn check_sent(
+        &mut self,
+        packet: &Packet,
+        from_drone: bool,
+        topology: &HashMap<NodeId, NodeView>,
+        time: f32,
+    ) {
+        // Flood requests travel without a routing header
+        if let PacketType::FloodRequest(_) = packet.pack_type {
+            return;
+        }
+        let header = &packet.routing_header;
+        if header.hop_index == 0 || header.hop_index >= header.hops.len() {
+            self.alert(
+                time,
+                ViolationKind::SenderNotOnHeader,
+                header.hops.clone(),
+                format!(
+                    "Hop index {} does not point to a sender with a next hop in {:?}",
+                    header.hop_index, header.hops
+                ),
+                packet,
+            );
+            return;
+        }
+        let sender = header.hops[header.hop_index - 1];
+        let receiver = header.hops[header.hop_index];
+
+        match topology.get(&sender) {
+            Some(view) => {
+                if !view.neighbours.contains(&receiver) {
+                    self.alert(
+                        time,
+                        ViolationKind::NotANeighbour,
+                        vec![sender, receiver],
+                        format!("{sender} sent to {receiver} which is not its neighbour"),
+                        packet,
+                    );
+                }
+                // A drone event with a leaf as sender means the drone kept the leaf's hop index
+                if from_drone && view.is_leaf {
+                    self.alert(
+                        time,
+                        ViolationKind::HopIndexNotAdvanced,
+                        vec![sender, receiver],
+                        format!(
+                            "A drone sent with hop index {} of leaf {sender}",
+                            header.hop_index
+                        ),
+                        packet,
+                    );
+                }
+            }
+            None if self.crashed.contains_key(&sender) => {
+                let after_grace = time - self.crashed[&sender] > CRASH_GRACE_SECS;
+                if after_grace && matches!(packet.pack_type, PacketType::MsgFragment(_)) {
+                    self.alert(
+                        time,
+                        ViolationKind::ForwardAfterCrash,
+                        vec![sender],
+                        format!("Crashed drone {sender} forwarded a fragment"),
+                        packet,
+                    );
+                }
+            }
+            None => {
+                self.alert(
+                    time,
+                    ViolationKind::SenderNotOnHeader,
+                    vec![sender],
+                    format!("Sender {sender} from the header is not in the topology"),
+                    packet,
+                );
+            }
+        }
+
+        if let Some(key) = packet_key(packet) {
+            if self.received_hop_index.len() > MAX_TRACKED_PACKETS {
+                self.received_hop_index.clear();
+            }
+            // A drone forwards with a higher hop index than it received the packet with
+            if let Some(&received) = self.received_hop_index.get(&(key, sender)) {
+                if header.hop_index > 1 && header.hop_index <= received {
+                    self.alert(
+                        time,
+                        ViolationKind::HopIndexNotAdvanced,
+                        vec![sender, receiver],
+                        format!(
+                            "Hop index {} after {received} for the same packet",
+                            header.hop_index
+                        ),
+                        packet,
+                    );
+                }
+            }
+            self.received_hop_index
+                .insert((key, receiver), header.hop_index);
+        }
+    }
+
+    /// Checks a packet dropped by a drone.
+    pub fn check_dropped(&mut self, packet: &Packet, time: f32) {
+        if let PacketType::MsgFragment(_) = packet.pack_type {
+            return;
+        }
+        let header = &packet.routing_header;
+        let nodes = header
+            .hop_index
+            .checked_sub(1)
+            .and_then(|i| header.hops.get(i))
+            .map(|id| vec![*id])
+            .unwrap_or_default();
+        self.alert(
+            time,
+            ViolationKind::DroppedNotFragment,
+            nodes,
+            "Only fragments can be dropped".to_string(),
+            packet,
+        );
+    }
+}
+
+fn packet_key(packet: &Packet) -> Option<(u64, u8, u64)> {
+    match &packet.pack_type {
+        PacketType::MsgFragment(fragment) => Some((packet.session_id, 0, fragment.fragment_index)),
+        PacketType::Ack(ack) => Some((packet.session_id, 1, ack.fragment_index)),
+        PacketType::Nack(nack) => Some((packet.session_id, 2, nack.fragment_index)),
+        PacketType::FloodRequest(_) | PacketType::FloodResponse(_) => None,
+    }
+}
@@ -0,0 +1,50 @@
+use super::resources::ProtocolMonitor;
+use crate::components::{Leaf, Node};
+use crate::resources::NodeIndex;
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+
+pub fn track_crashes(
+    nodes: Query<(&Node, Has<Leaf>)>,
+    index: Res<NodeIndex>,
+    mut monitor: ResMut<ProtocolMonitor>,
+    time: Res<Time>,
+) {
+    let monitor = &mut *monitor;
+    for (node, is_leaf) in nodes.iter() {
+        monitor.known.insert(node.id, is_leaf);
+        // A new drone can take the id of a crashed one
+        monitor.crashed.remove(&node.id);
+    }
+    // Nodes only leave the topology by crashing
+    let now = time.elapsed_secs();
+    for (id, is_leaf) in &monitor.known {
+        if !*is_leaf && !index.contains(*id) {
+            monitor.crashed.entry(*id).or_insert(now);
+        }
+    }
+}
+
+pub fn alerts_window(mut contexts: EguiContexts, mut monitor: ResMut<ProtocolMonitor>) {
+    egui::Window::new(format!("Alerts ({})", monitor.alerts.len()))
+        .id(egui::Id::new("alerts_window"))
+        .default_open(false)
+        .show(contexts.ctx_mut(), |ui| {
+            if ui.button("Clear").clicked() {
+                monitor.alerts.clear();
+            }
+            ui.separator();
+            egui::ScrollArea::vertical()
+                .max_height(400.0)
+                .show(ui, |ui| {
+                    for alert in monitor.alerts.iter().rev() {
+                        ui.colored_label(
+                            egui::Color32::LIGHT_RED,
+                            format!("[{:.2}s] {}: {}", alert.time, alert.kind, alert.description),
+                        );
+                        ui.label(&alert.packet);
+                        ui.separator();
+                    }
+                });
+        });
+}
@@ -0,0 +1,19 @@
+/// This module contains the queue monitor plugin.
+/// It samples the input channel length of every drone, keeps its history and statistics,
+/// and raises an alarm when a queue stays above a threshold for too long.
+mod resources;
+mod systems;
+
+use bevy::prelude::*;
+pub use resources::QueueMonitor;
+use systems::{alarms_window, queues_window, sample_queues};
+
+pub struct QueuesPlugin;
+
+impl Plugin for QueuesPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(QueueMonitor::default());
+        app.add_systems(Update, sample_queues);
+        app.add_systems(Update, (queues_window, alarms_window));
+    }
+}
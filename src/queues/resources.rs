@@ -0,0 +1,101 @@
+use bevy::prelude::*;
+use std::collections::{BTreeMap, VecDeque};
+use wg_2024::network::NodeId;
+
+pub const SAMPLE_INTERVAL: f32 = 0.25;
+// Samples kept for the history plot, two minutes at the sample interval
+pub const HISTORY_LEN: usize = 480;
+
+#[derive(Debug, Clone, Default)]
+pub struct QueueStats {
+    pub history: VecDeque<usize>,
+    pub current: usize,
+    pub peak: usize,
+    sum: u64,
+    samples: u64,
+    // Seconds spent above each of the monitor thresholds
+    pub time_above: Vec<f32>,
+    // Time since which the queue is above the alarm threshold
+    pub above_since: Option<f32>,
+}
+
+impl QueueStats {
+    pub fn mean(&self) -> f32 {
+        if self.samples == 0 {
+            0.0
+        } else {
+            self.sum as f32 / self.samples as f32
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct QueueAlarm {
+    pub node_id: NodeId,
+    pub since: f32,
+    pub threshold: usize,
+}
+
+#[derive(Resource)]
+pub struct QueueMonitor {
+    pub timer: Timer,
+    pub thresholds: Vec<usize>,
+    pub alarm_threshold: usize,
+    pub alarm_secs: f32,
+    pub stats: BTreeMap<NodeId, QueueStats>,
+    pub alarms: Vec<QueueAlarm>,
+}
+
+impl Default for QueueMonitor {
+    fn default() -> Self {
+        QueueMonitor {
+            timer: Timer::from_seconds(SAMPLE_INTERVAL, TimerMode::Repeating),
+            thresholds: vec![10, 20, 50],
+            alarm_threshold: 20,
+            alarm_secs: 5.0,
+            stats: BTreeMap::new(),
+            alarms: Vec::new(),
+        }
+    }
+}
+
+impl QueueMonitor {
+    /// Adds a sample of the queue of a drone, taken at time `now`.
+    pub fn record(&mut self, node_id: NodeId, len: usize, now: f32) {
+        let thresholds = self.thresholds.clone();
+        let stats = self.stats.entry(node_id).or_default();
+        if stats.history.len() >= HISTORY_LEN {
+            stats.history.pop_front();
+        }
+        stats.history.push_back(len);
+        stats.current = len;
+        stats.peak = stats.peak.max(len);
+        stats.sum += len as u64;
+        stats.samples += 1;
+        stats.time_above.resize(thresholds.len(), 0.0);
+        for (i, threshold) in thresholds.iter().enumerate() {
+            if len > *threshold {
+                stats.time_above[i] += SAMPLE_INTERVAL;
+            }
+        }
+
+        if len > self.alarm_threshold {
+            let since = *stats.above_since.get_or_insert(now);
+            let alarmed = self.alarms.iter().any(|alarm| alarm.node_id == node_id);
+            if now - since >= self.alarm_secs && !alarmed {
+                println!(
+                    "Queue alarm: drone {node_id} above {} since {since:.1}s",
+                    self.alarm_threshold
+                );
+                self.alarms.push(QueueAlarm {
+                    node_id,
+                    since,
+                    threshold: self.alarm_threshold,
+                });
+            }
+        } else {
+            stats.above_since = None;
+            self.alarms.retain(|alarm| alarm.node_id != node_id);
+        }
+    }
+}
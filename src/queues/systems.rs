@@ -0,0 +1,111 @@
+use super::resources::{QueueMonitor, HISTORY_LEN};
+use crate::components::{Leaf, Node};
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+
+pub fn sample_queues(
+    drones: Query<&Node, Without<Leaf>>,
+    mut monitor: ResMut<QueueMonitor>,
+    time: Res<Time>,
+) {
+    if !monitor.timer.tick(time.delta()).just_finished() {
+        return;
+    }
+    let now = time.elapsed_secs();
+    for node in drones.iter() {
+        monitor.record(node.id, node.packet_channel.len(), now);
+    }
+    // Crashed drones don't need an alarm anymore
+    monitor
+        .alarms
+        .retain(|alarm| drones.iter().any(|node| node.id == alarm.node_id));
+}
+
+pub fn queues_window(mut contexts: EguiContexts, mut monitor: ResMut<QueueMonitor>) {
+    egui::Window::new("Queues")
+        .default_open(false)
+        .show(contexts.ctx_mut(), |ui| {
+            ui.horizontal(|ui| {
+                ui.label("Thresholds:");
+                for threshold in &mut monitor.thresholds {
+                    ui.add(egui::DragValue::new(threshold).range(1..=1000));
+                }
+            });
+            ui.horizontal(|ui| {
+                ui.label("Alarm when above");
+                ui.add(egui::DragValue::new(&mut monitor.alarm_threshold).range(1..=1000));
+                ui.label("for");
+                ui.add(
+                    egui::DragValue::new(&mut monitor.alarm_secs)
+                        .range(1.0..=600.0)
+                        .suffix("s"),
+                );
+            });
+            ui.separator();
+            egui::ScrollArea::vertical()
+                .max_height(400.0)
+                .show(ui, |ui| {
+                    egui::Grid::new("queues_grid").striped(true).show(ui, |ui| {
+                        ui.label("Drone");
+                        ui.label("Now");
+                        ui.label("Peak");
+                        ui.label("Mean");
+                        for threshold in &monitor.thresholds {
+                            ui.label(format!("> {threshold}"));
+                        }
+                        ui.label("History");
+                        ui.end_row();
+                        for (id, stats) in &monitor.stats {
+                            ui.label(id.to_string());
+                            ui.label(stats.current.to_string());
+                            ui.label(stats.peak.to_string());
+                            ui.label(format!("{:.1}", stats.mean()));
+                            for time in &stats.time_above {
+                                ui.label(format!("{time:.1}s"));
+                            }
+                            history_plot(ui, stats.history.iter().copied(), stats.peak);
+                            ui.end_row();
+                        }
+                    });
+                });
+        });
+}
+
+fn history_plot(ui: &mut egui::Ui, history: impl Iterator<Item = usize>, peak: usize) {
+    let (rect, _) = ui.allocate_exact_size(egui::vec2(160.0, 20.0), egui::Sense::hover());
+    let peak = peak.max(1) as f32;
+    let points: Vec<egui::Pos2> = history
+        .enumerate()
+        .map(|(i, len)| {
+            egui::pos2(
+                rect.left() + rect.width() * i as f32 / HISTORY_LEN as f32,
+                rect.bottom() - rect.height() * len as f32 / peak,
+            )
+        })
+        .collect();
+    ui.painter()
+        .line(points, egui::Stroke::new(1.0, egui::Color32::LIGHT_BLUE));
+}
+
+pub fn alarms_window(mut contexts: EguiContexts, monitor: Res<QueueMonitor>, time: Res<Time>) {
+    if monitor.alarms.is_empty() {
+        return;
+    }
+    egui::Window::new("Queue alarm")
+        .anchor(egui::Align2::CENTER_TOP, egui::vec2(0.0, 10.0))
+        .collapsible(false)
+        .resizable(false)
+        .show(contexts.ctx_mut(), |ui| {
+            for alarm in &monitor.alarms {
+                ui.colored_label(
+                    egui::Color32::LIGHT_RED,
+                    format!(
+                        "Drone {} queue above {} for {:.0}s, it may be stalled",
+                        alarm.node_id,
+                        alarm.threshold,
+                        time.elapsed_secs() - alarm.since
+                    ),
+                );
+            }
+        });
+}
@@ -1,9 +1,14 @@
+use bevy::ecs::{component::ComponentId, world::DeferredWorld};
 use bevy::prelude::*;
 
+use crate::components::Node;
 use common_structs::leaf::LeafEvent;
 use crossbeam_channel::{Receiver, Sender};
+use force_graph::DefaultNodeIdx;
 use network_initializer::network::Network;
+use std::collections::HashMap;
 use wg_2024::controller::DroneEvent;
+use wg_2024::network::NodeId;
 
 #[derive(Resource)]
 pub struct DroneListener {
@@ -25,3 +30,55 @@ pub struct Senders {
 pub struct NetworkResource {
     pub data: Network,
 }
+
+// Finds drones and leaves by id without going through all the nodes.
+// It is kept up to date by the hooks of `Node` and of the force graph marker.
+#[derive(Resource, Default)]
+pub struct NodeIndex {
+    entities: HashMap<NodeId, Entity>,
+    graph: HashMap<NodeId, DefaultNodeIdx>,
+}
+
+impl NodeIndex {
+    pub fn entity(&self, id: NodeId) -> Option<Entity> {
+        self.entities.get(&id).copied()
+    }
+
+    /// Index of the node in the force graph, once it has been added there.
+    pub fn graph_index(&self, id: NodeId) -> Option<DefaultNodeIdx> {
+        self.graph.get(&id).copied()
+    }
+
+    pub fn contains(&self, id: NodeId) -> bool {
+        self.entities.contains_key(&id)
+    }
+
+    pub fn set_graph_index(&mut self, id: NodeId, index: Option<DefaultNodeIdx>) {
+        match index {
+            Some(index) => self.graph.insert(id, index),
+            None => self.graph.remove(&id),
+        };
+    }
+}
+
+pub fn index_node(mut world: DeferredWorld, entity: Entity, _: ComponentId) {
+    let Some(id) = world.get::<Node>(entity).map(|node| node.id) else {
+        return;
+    };
+    if let Some(mut index) = world.get_resource_mut::<NodeIndex>() {
+        index.entities.insert(id, entity);
+    }
+}
+
+pub fn unindex_node(mut world: DeferredWorld, entity: Entity, _: ComponentId) {
+    let Some(id) = world.get::<Node>(entity).map(|node| node.id) else {
+        return;
+    };
+    if let Some(mut index) = world.get_resource_mut::<NodeIndex>() {
+        // A new node could already be using the id of the one being despawned
+        if index.entities.get(&id) == Some(&entity) {
+            index.entities.remove(&id);
+            index.graph.remove(&id);
+        }
+    }
+}
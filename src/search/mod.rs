@@ -0,0 +1,18 @@
+/// This module contains the search plugin.
+/// Nodes can be searched by id, implementation name, type or PDR range; choosing a result
+/// selects the node and centres the camera on it, and the filters can dim everything else.
+mod resources;
+mod systems;
+
+use bevy::prelude::*;
+use resources::SearchState;
+use systems::{dim_unmatched, search_window};
+
+pub struct SearchPlugin;
+
+impl Plugin for SearchPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(SearchState::default());
+        app.add_systems(Update, (search_window, dim_unmatched).chain());
+    }
+}
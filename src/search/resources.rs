@@ -0,0 +1,86 @@
+use crate::components::{LeafType, Node};
+use bevy::prelude::*;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum KindFilter {
+    Any,
+    Drone,
+    Client,
+    Server,
+}
+
+impl KindFilter {
+    pub const ALL: [KindFilter; 4] = [
+        KindFilter::Any,
+        KindFilter::Drone,
+        KindFilter::Client,
+        KindFilter::Server,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            KindFilter::Any => "Any",
+            KindFilter::Drone => "Drone",
+            KindFilter::Client => "Client",
+            KindFilter::Server => "Server",
+        }
+    }
+}
+
+#[derive(Resource)]
+pub struct SearchState {
+    pub query: String,
+    pub kind: KindFilter,
+    // Only drones have a PDR, leaves never match a narrowed range
+    pub pdr_min: f32,
+    pub pdr_max: f32,
+    pub dim: bool,
+}
+
+impl Default for SearchState {
+    fn default() -> Self {
+        SearchState {
+            query: String::new(),
+            kind: KindFilter::Any,
+            pdr_min: 0.0,
+            pdr_max: 1.0,
+            dim: false,
+        }
+    }
+}
+
+impl SearchState {
+    pub fn is_active(&self) -> bool {
+        !self.query.trim().is_empty()
+            || self.kind != KindFilter::Any
+            || self.pdr_min > 0.0
+            || self.pdr_max < 1.0
+    }
+
+    /// Every word of the query has to match the id, the implementation name or the type.
+    pub fn matches(&self, node: &Node, pdr: Option<f32>, leaf_type: Option<&LeafType>) -> bool {
+        let kind = match leaf_type {
+            None => KindFilter::Drone,
+            Some(LeafType::Client) => KindFilter::Client,
+            Some(LeafType::Server) => KindFilter::Server,
+        };
+        if self.kind != KindFilter::Any && self.kind != kind {
+            return false;
+        }
+        if self.pdr_min > 0.0 || self.pdr_max < 1.0 {
+            match pdr {
+                Some(pdr) if (self.pdr_min..=self.pdr_max).contains(&pdr) => {}
+                _ => return false,
+            }
+        }
+        let name = node.name_impl.to_lowercase();
+        let kind_name = kind.name().to_lowercase();
+        self.query.split_whitespace().all(|word| {
+            let word = word.to_lowercase();
+            match word.parse::<u8>() {
+                Ok(id) => node.id == id,
+                Err(_) => name.contains(&word) || kind_name.contains(&word),
+            }
+        })
+    }
+}
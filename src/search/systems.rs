@@ -0,0 +1,145 @@
+use super::resources::{KindFilter, SearchState};
+use crate::components::{Dimmed, Drone, Edge, Leaf, Node, Text, DIM_ALPHA};
+use crate::events::SelectNodeEvent;
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+use std::collections::HashSet;
+
+// Results past this number are not listed
+const MAX_RESULTS: usize = 50;
+
+pub fn search_window(
+    mut contexts: EguiContexts,
+    mut state: ResMut<SearchState>,
+    nodes: Query<(&Node, Option<&Drone>, Option<&Leaf>, &Transform)>,
+    mut camera: Query<&mut Transform, (With<Camera>, Without<Node>)>,
+    mut ew_select: EventWriter<SelectNodeEvent>,
+) {
+    egui::Window::new("Search")
+        .default_open(false)
+        .show(contexts.ctx_mut(), |ui| {
+            let state = &mut *state;
+            ui.horizontal(|ui| {
+                ui.label("Find:");
+                ui.text_edit_singleline(&mut state.query)
+                    .on_hover_text("Id, implementation name or type, e.g. \"drone 37\"");
+            });
+            ui.horizontal(|ui| {
+                ui.label("Type:");
+                egui::ComboBox::from_id_salt("search_kind")
+                    .selected_text(state.kind.name())
+                    .show_ui(ui, |ui| {
+                        for kind in KindFilter::ALL {
+                            ui.selectable_value(&mut state.kind, kind, kind.name());
+                        }
+                    });
+            });
+            ui.horizontal(|ui| {
+                ui.label("PDR from");
+                ui.add(
+                    egui::DragValue::new(&mut state.pdr_min)
+                        .range(0.0..=1.0)
+                        .speed(0.01),
+                );
+                ui.label("to");
+                ui.add(
+                    egui::DragValue::new(&mut state.pdr_max)
+                        .range(0.0..=1.0)
+                        .speed(0.01),
+                );
+            });
+            ui.checkbox(&mut state.dim, "Dim the nodes that don't match");
+            if ui.button("Clear").clicked() {
+                *state = SearchState {
+                    dim: state.dim,
+                    ..Default::default()
+                };
+            }
+            ui.separator();
+
+            if !state.is_active() {
+                ui.label("Type something to search");
+                return;
+            }
+            let mut results: Vec<_> = nodes
+                .iter()
+                .filter(|(node, drone, leaf, _)| {
+                    state.matches(
+                        node,
+                        drone.map(|drone| drone.pdr),
+                        leaf.map(|leaf| &leaf.leaf_type),
+                    )
+                })
+                .collect();
+            results.sort_by_key(|(node, ..)| node.id);
+            ui.label(format!("{} nodes found", results.len()));
+            egui::ScrollArea::vertical()
+                .max_height(300.0)
+                .show(ui, |ui| {
+                    for (node, drone, leaf, transform) in results.into_iter().take(MAX_RESULTS) {
+                        let kind =
+                            leaf.map_or("Drone".to_string(), |leaf| leaf.leaf_type.to_string());
+                        let pdr =
+                            drone.map_or(String::new(), |drone| format!(", PDR {:.2}", drone.pdr));
+                        let text = format!("{kind} {}: {}{pdr}", node.id, node.name_impl);
+                        if ui.button(text).clicked() {
+                            ew_select.send(SelectNodeEvent {
+                                entity: node.entity_id,
+                                additive: false,
+                            });
+                            for mut camera in &mut camera {
+                                camera.translation.x = transform.translation.x;
+                                camera.translation.y = transform.translation.y;
+                            }
+                        }
+                    }
+                });
+        });
+}
+
+pub fn dim_unmatched(
+    mut commands: Commands,
+    state: Res<SearchState>,
+    nodes: Query<(Entity, &Node, Option<&Drone>, Option<&Leaf>, Has<Dimmed>)>,
+    edges: Query<(Entity, &Edge, Has<Dimmed>)>,
+    mut texts: Query<(&Text, &mut TextColor)>,
+) {
+    let filtering = state.dim && state.is_active();
+    let mut dimmed_nodes = HashSet::new();
+    let mut dimmed_entities = HashSet::new();
+    for (entity, node, drone, leaf, dimmed) in nodes.iter() {
+        let dim = filtering
+            && !state.matches(
+                node,
+                drone.map(|drone| drone.pdr),
+                leaf.map(|leaf| &leaf.leaf_type),
+            );
+        if dim {
+            dimmed_nodes.insert(node.id);
+            dimmed_entities.insert(entity);
+        }
+        set_dimmed(&mut commands, entity, dimmed, dim);
+    }
+    for (entity, edge, dimmed) in edges.iter() {
+        let dim = dimmed_nodes.contains(&edge.start_node) || dimmed_nodes.contains(&edge.end_node);
+        set_dimmed(&mut commands, entity, dimmed, dim);
+    }
+    for (text, mut color) in &mut texts {
+        let alpha = if dimmed_entities.contains(&text.entity_id) {
+            DIM_ALPHA
+        } else {
+            1.0
+        };
+        if color.0.alpha() != alpha {
+            color.0.set_alpha(alpha);
+        }
+    }
+}
+
+fn set_dimmed(commands: &mut Commands, entity: Entity, dimmed: bool, dim: bool) {
+    if dim && !dimmed {
+        commands.entity(entity).insert(Dimmed);
+    } else if !dim && dimmed {
+        commands.entity(entity).remove::<Dimmed>();
+    }
+}
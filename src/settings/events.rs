@@ -8,3 +8,12 @@ pub struct ModeEvent;
 
 #[derive(Event)]
 pub struct ResetInfosEvent;
+
+#[derive(Event)]
+pub struct MetricsEvent;
+
+#[derive(Event)]
+pub struct ExportEvent;
+
+#[derive(Event)]
+pub struct ResetLayoutEvent;
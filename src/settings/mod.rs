@@ -3,6 +3,7 @@
 mod events;
 mod resources;
 mod systems;
+pub use events::{ExportEvent, MetricsEvent, ResetLayoutEvent};
 pub use resources::{ModeConfig, MusicResource};
 
 use bevy::prelude::*;
@@ -26,6 +27,9 @@ impl Plugin for SettingsPlugin {
         app.add_event::<MusicEvent>();
         app.add_event::<ModeEvent>();
         app.add_event::<ResetInfosEvent>();
+        app.add_event::<MetricsEvent>();
+        app.add_event::<ExportEvent>();
+        app.add_event::<ResetLayoutEvent>();
         app.add_systems(Update, settings_window);
         app.add_systems(Startup, spawn_soundtrack);
         app.add_systems(Update, update_soundtrack);
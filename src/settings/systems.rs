@@ -1,6 +1,15 @@
-use super::events::{ModeEvent, MusicEvent, ResetInfosEvent};
+use super::events::{
+    ExportEvent, MetricsEvent, ModeEvent, MusicEvent, ResetInfosEvent, ResetLayoutEvent,
+};
 use super::resources::{ModeConfig, MusicResource, StateResource};
-use crate::event_listener::{resources::Bytes, DisplayedInfo};
+use crate::event_listener::{
+    resources::{Bytes, PacketTypeCounters},
+    DisplayedInfo,
+};
+use crate::export::ExportConfig;
+use crate::flood::FloodTracker;
+use crate::metrics::MetricsResource;
+use crate::physics::{LayoutPreset, MyForceGraph};
 use bevy::prelude::*;
 use bevy_egui::{egui, EguiContexts};
 
@@ -11,6 +20,12 @@ pub fn settings_window(
     mut ew_music: EventWriter<MusicEvent>,
     mut ew_unchecked_mode: EventWriter<ModeEvent>,
     mut ew_infos: EventWriter<ResetInfosEvent>,
+    mut metrics: ResMut<MetricsResource>,
+    mut ew_metrics: EventWriter<MetricsEvent>,
+    mut export_config: ResMut<ExportConfig>,
+    mut ew_export: EventWriter<ExportEvent>,
+    mut ew_layout: EventWriter<ResetLayoutEvent>,
+    mut force_graph: ResMut<MyForceGraph>,
 ) {
     egui::Window::new("Settings").show(contexts.ctx_mut(), |ui| {
         if ui.checkbox(&mut music_ui.playing, "Music").clicked() {
@@ -22,9 +37,67 @@ pub fn settings_window(
         {
             ew_unchecked_mode.send(ModeEvent);
         };
+        if ui
+            .checkbox(&mut metrics.enabled, "Metrics endpoint (127.0.0.1:9898)")
+            .clicked()
+        {
+            ew_metrics.send(MetricsEvent);
+        };
         if ui.button("Reset infos").clicked() {
             ew_infos.send(ResetInfosEvent);
         }
+        ui.horizontal(|ui| {
+            if ui.button("Export stats").clicked() {
+                ew_export.send(ExportEvent);
+            }
+            ui.checkbox(&mut export_config.on_exit, "Export on exit");
+        });
+        if ui
+            .button("Reset layout")
+            .on_hover_text("Scatter the nodes again, pinned nodes stay where they are")
+            .clicked()
+        {
+            ew_layout.send(ResetLayoutEvent);
+        }
+        egui::CollapsingHeader::new("Layout").show(ui, |ui| {
+            let force_graph = &mut *force_graph;
+            let parameters = &mut force_graph.data.parameters;
+            ui.horizontal(|ui| {
+                for preset in LayoutPreset::ALL {
+                    if ui.button(preset.name()).clicked() {
+                        preset.apply(parameters);
+                    }
+                }
+            });
+            ui.add(
+                egui::Slider::new(&mut parameters.force_charge, 100.0..=20000.0)
+                    .logarithmic(true)
+                    .text("Repulsion"),
+            );
+            ui.add(
+                egui::Slider::new(&mut parameters.force_spring, 0.01..=1.0)
+                    .logarithmic(true)
+                    .text("Spring"),
+            );
+            ui.add(egui::Slider::new(&mut parameters.force_max, 10.0..=500.0).text("Max force"));
+            ui.add(
+                egui::Slider::new(&mut parameters.node_speed, 100.0..=10000.0)
+                    .logarithmic(true)
+                    .text("Node speed"),
+            );
+            ui.add(egui::Slider::new(&mut parameters.damping_factor, 0.5..=0.999).text("Damping"));
+            ui.add(egui::Slider::new(&mut parameters.theta, 0.3..=1.5).text("Barnes-Hut theta"))
+                .on_hover_text("Lower is more accurate but slower, only used on large graphs");
+            ui.checkbox(&mut force_graph.follow_view, "Centre follows the view");
+            ui.add_enabled_ui(!force_graph.follow_view, |ui| {
+                let parameters = &mut force_graph.data.parameters;
+                ui.horizontal(|ui| {
+                    ui.label("Centre");
+                    ui.add(egui::DragValue::new(&mut parameters.center_x).prefix("x: "));
+                    ui.add(egui::DragValue::new(&mut parameters.center_y).prefix("y: "));
+                });
+            });
+        });
     });
 }
 pub fn spawn_soundtrack(
@@ -79,20 +152,30 @@ pub fn update_unchecked(mut reader: EventReader<ModeEvent>, mut state: ResMut<Mo
     }
 }
 
-pub fn reset_infos(mut reader: EventReader<ResetInfosEvent>, mut info: ResMut<DisplayedInfo>) {
+pub fn reset_infos(
+    mut reader: EventReader<ResetInfosEvent>,
+    mut info: ResMut<DisplayedInfo>,
+    mut floods: ResMut<FloodTracker>,
+) {
     for _ in reader.read() {
+        floods.floods.clear();
         for data in info.drone.values_mut() {
             data.packets_sent = 0;
             data.packets_shortcutted = 0;
             data.data_sent = Bytes(0);
             data.data_dropped = Bytes(0);
+            data.fragments_dropped = 0;
             data.neighbours.clear();
+            data.packet_types = PacketTypeCounters::default();
         }
         for data in info.leaf.values_mut() {
             data.packets_sent = 0;
             data.data_sent = Bytes(0);
             data.msg_n = 0;
+            data.neighbours.clear();
             data.messages.clear();
+            data.sessions_started.clear();
+            data.packet_types = PacketTypeCounters::default();
         }
     }
 }
@@ -0,0 +1,19 @@
+/// This module contains the shortcut plugin.
+/// Packets that nodes can't route (acks, nacks and flood responses) are shortcut through the
+/// controller: this plugin delivers them according to the chosen policy and keeps a log of them.
+mod resources;
+mod systems;
+
+use bevy::prelude::*;
+pub use resources::Shortcuts;
+use systems::{process_shortcuts, shortcut_window};
+
+pub struct ShortcutPlugin;
+
+impl Plugin for ShortcutPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Shortcuts::default());
+        app.add_systems(Update, process_shortcuts);
+        app.add_systems(Update, shortcut_window);
+    }
+}
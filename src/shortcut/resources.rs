@@ -0,0 +1,114 @@
+use crate::event_listener::resources::packet_type_name;
+use bevy::prelude::*;
+use std::collections::VecDeque;
+use std::fmt::Display;
+use wg_2024::network::NodeId;
+use wg_2024::packet::Packet;
+
+// Older log entries are forgotten once this many are stored
+const MAX_LOG: usize = 500;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ShortcutPolicy {
+    Deliver,
+    Delay,
+    Drop,
+    ManualApproval,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ShortcutOutcome {
+    Waiting,
+    Delivered,
+    Dropped,
+    Rejected,
+    Failed(String),
+}
+
+impl Display for ShortcutOutcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ShortcutOutcome::Waiting => write!(f, "Waiting"),
+            ShortcutOutcome::Delivered => write!(f, "Delivered"),
+            ShortcutOutcome::Dropped => write!(f, "Dropped by policy"),
+            ShortcutOutcome::Rejected => write!(f, "Rejected"),
+            ShortcutOutcome::Failed(reason) => write!(f, "Failed: {reason}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ShortcutEntry {
+    // Unique id used to find the entry when the packet is finally handled
+    pub id: u64,
+    pub time: f32,
+    pub packet_type: String,
+    pub origin: Option<NodeId>,
+    pub destination: Option<NodeId>,
+    pub outcome: ShortcutOutcome,
+}
+
+pub struct HeldPacket {
+    pub entry_id: u64,
+    // Time at which a delayed packet is delivered
+    pub due: f32,
+    pub packet: Packet,
+}
+
+#[derive(Resource)]
+pub struct Shortcuts {
+    pub policy: ShortcutPolicy,
+    pub delay_secs: f32,
+    pub log: VecDeque<ShortcutEntry>,
+    // Packets received from the listeners and not handled yet
+    pub incoming: Vec<(u64, Packet)>,
+    pub delayed: Vec<HeldPacket>,
+    pub pending: Vec<HeldPacket>,
+    next_id: u64,
+}
+
+impl Default for Shortcuts {
+    fn default() -> Self {
+        Shortcuts {
+            policy: ShortcutPolicy::Deliver,
+            delay_secs: 1.0,
+            log: VecDeque::new(),
+            incoming: Vec::new(),
+            delayed: Vec::new(),
+            pending: Vec::new(),
+            next_id: 0,
+        }
+    }
+}
+
+impl Shortcuts {
+    /// Logs a packet shortcut by a node, it is handled later according to the policy.
+    pub fn submit(&mut self, packet: Packet, time: f32) {
+        let header = &packet.routing_header;
+        let origin = header
+            .hop_index
+            .checked_sub(1)
+            .and_then(|i| header.hops.get(i))
+            .copied();
+        let id = self.next_id;
+        self.next_id += 1;
+        if self.log.len() >= MAX_LOG {
+            self.log.pop_front();
+        }
+        self.log.push_back(ShortcutEntry {
+            id,
+            time,
+            packet_type: packet_type_name(&packet),
+            origin,
+            destination: header.destination(),
+            outcome: ShortcutOutcome::Waiting,
+        });
+        self.incoming.push((id, packet));
+    }
+
+    pub fn set_outcome(&mut self, id: u64, outcome: ShortcutOutcome) {
+        if let Some(entry) = self.log.iter_mut().rev().find(|entry| entry.id == id) {
+            entry.outcome = outcome;
+        }
+    }
+}
@@ -0,0 +1,170 @@
+use super::resources::{HeldPacket, ShortcutOutcome, ShortcutPolicy, Shortcuts};
+use crate::components::Node;
+use crate::resources::NodeIndex;
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+use wg_2024::packet::Packet;
+
+pub fn process_shortcuts(
+    mut shortcuts: ResMut<Shortcuts>,
+    node_query: Query<&Node>,
+    index: Res<NodeIndex>,
+    time: Res<Time>,
+) {
+    let now = time.elapsed_secs();
+    let incoming: Vec<(u64, Packet)> = shortcuts.incoming.drain(..).collect();
+    for (entry_id, packet) in incoming {
+        match shortcuts.policy {
+            ShortcutPolicy::Deliver => {
+                let outcome = shortcut(&node_query, &index, &packet);
+                shortcuts.set_outcome(entry_id, outcome);
+            }
+            ShortcutPolicy::Delay => {
+                let due = now + shortcuts.delay_secs;
+                shortcuts.delayed.push(HeldPacket {
+                    entry_id,
+                    due,
+                    packet,
+                });
+            }
+            ShortcutPolicy::Drop => shortcuts.set_outcome(entry_id, ShortcutOutcome::Dropped),
+            ShortcutPolicy::ManualApproval => shortcuts.pending.push(HeldPacket {
+                entry_id,
+                due: now,
+                packet,
+            }),
+        }
+    }
+
+    let (due, delayed): (Vec<HeldPacket>, Vec<HeldPacket>) = shortcuts
+        .delayed
+        .drain(..)
+        .partition(|held| held.due <= now);
+    shortcuts.delayed = delayed;
+    for held in due {
+        let outcome = shortcut(&node_query, &index, &held.packet);
+        shortcuts.set_outcome(held.entry_id, outcome);
+    }
+}
+
+fn shortcut(node_query: &Query<&Node>, index: &NodeIndex, packet: &Packet) -> ShortcutOutcome {
+    let Some(dest) = &packet.routing_header.destination() else {
+        eprintln!("### SHORTCUT: NO DESTINATION");
+        return ShortcutOutcome::Failed("no destination".to_string());
+    };
+
+    let Some(node) = index
+        .entity(*dest)
+        .and_then(|entity| node_query.get(entity).ok())
+    else {
+        eprintln!("### SHORTCUT: DIDN'T FIND DESTINATION");
+        return ShortcutOutcome::Failed(format!("destination {dest} not found"));
+    };
+
+    if node.packet_channel.send(packet.clone()).is_ok() {
+        println!(
+            "### SHORTCUT: Node with ID: {}, has received the packet: {}",
+            node.id, packet
+        );
+        ShortcutOutcome::Delivered
+    } else {
+        eprintln!("### SHORTCUT: failed to shortcut");
+        ShortcutOutcome::Failed("destination channel closed".to_string())
+    }
+}
+
+pub fn shortcut_window(
+    mut contexts: EguiContexts,
+    mut shortcuts: ResMut<Shortcuts>,
+    node_query: Query<&Node>,
+    index: Res<NodeIndex>,
+) {
+    egui::Window::new("Shortcuts")
+        .default_open(false)
+        .show(contexts.ctx_mut(), |ui| {
+            ui.horizontal(|ui| {
+                ui.label("Policy:");
+                ui.selectable_value(&mut shortcuts.policy, ShortcutPolicy::Deliver, "Deliver");
+                ui.selectable_value(&mut shortcuts.policy, ShortcutPolicy::Delay, "Delay");
+                ui.selectable_value(&mut shortcuts.policy, ShortcutPolicy::Drop, "Drop");
+                ui.selectable_value(
+                    &mut shortcuts.policy,
+                    ShortcutPolicy::ManualApproval,
+                    "Manual",
+                );
+            });
+            if shortcuts.policy == ShortcutPolicy::Delay {
+                ui.add(egui::Slider::new(&mut shortcuts.delay_secs, 0.1..=30.0).text("Delay (s)"));
+            }
+            ui.label(format!("Delayed packets: {}", shortcuts.delayed.len()));
+            ui.separator();
+
+            ui.heading(format!("Waiting for approval: {}", shortcuts.pending.len()));
+            let mut approved = Vec::new();
+            let mut rejected = Vec::new();
+            ui.horizontal(|ui| {
+                if ui.button("Approve all").clicked() {
+                    approved.extend(0..shortcuts.pending.len());
+                }
+                if ui.button("Reject all").clicked() {
+                    rejected.extend(0..shortcuts.pending.len());
+                }
+            });
+            egui::ScrollArea::vertical()
+                .id_salt("pending_shortcuts")
+                .max_height(150.0)
+                .show(ui, |ui| {
+                    for (i, held) in shortcuts.pending.iter().enumerate() {
+                        ui.horizontal(|ui| {
+                            ui.label(held.packet.to_string());
+                            if ui.button("Approve").clicked() {
+                                approved.push(i);
+                            }
+                            if ui.button("Reject").clicked() {
+                                rejected.push(i);
+                            }
+                        });
+                    }
+                });
+            let pending: Vec<HeldPacket> = shortcuts.pending.drain(..).collect();
+            for (i, held) in pending.into_iter().enumerate() {
+                if approved.contains(&i) {
+                    let outcome = shortcut(&node_query, &index, &held.packet);
+                    shortcuts.set_outcome(held.entry_id, outcome);
+                } else if rejected.contains(&i) {
+                    shortcuts.set_outcome(held.entry_id, ShortcutOutcome::Rejected);
+                } else {
+                    shortcuts.pending.push(held);
+                }
+            }
+            ui.separator();
+
+            ui.heading("Log:");
+            egui::ScrollArea::vertical()
+                .id_salt("shortcut_log")
+                .max_height(300.0)
+                .show(ui, |ui| {
+                    egui::Grid::new("shortcut_log_grid")
+                        .striped(true)
+                        .show(ui, |ui| {
+                            ui.label("Time");
+                            ui.label("Type");
+                            ui.label("From");
+                            ui.label("To");
+                            ui.label("Outcome");
+                            ui.end_row();
+                            for entry in shortcuts.log.iter().rev() {
+                                let id = |id: Option<u8>| {
+                                    id.map_or("-".to_string(), |id| id.to_string())
+                                };
+                                ui.label(format!("{:.2}s", entry.time));
+                                ui.label(&entry.packet_type);
+                                ui.label(id(entry.origin));
+                                ui.label(id(entry.destination));
+                                ui.label(entry.outcome.to_string());
+                                ui.end_row();
+                            }
+                        });
+                });
+        });
+}
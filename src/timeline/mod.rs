@@ -0,0 +1,26 @@
+/// This module contains the timeline plugin.
+/// Every second it journals the topology and the statistics, marking crashes, edge changes
+/// and alerts, and the timeline bar lets the user scrub back to any recorded moment.
+/// While looking at the past the live graph and the side panel are replaced by a read-only view.
+mod resources;
+mod systems;
+
+use bevy::prelude::*;
+pub use resources::Timeline;
+use systems::{draw_history, history_panel, record_frame, sync_live_visibility, timeline_bar};
+
+pub struct TimelinePlugin;
+
+impl Plugin for TimelinePlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Timeline::default());
+        app.add_systems(FixedUpdate, record_frame);
+        app.add_systems(Update, (timeline_bar, history_panel).chain());
+        app.add_systems(Update, (sync_live_visibility, draw_history));
+    }
+}
+
+/// Run condition for the systems that only make sense on the live state.
+pub fn timeline_live(timeline: Res<Timeline>) -> bool {
+    timeline.viewing.is_none()
+}
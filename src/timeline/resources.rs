@@ -0,0 +1,155 @@
+use crate::event_listener::resources::{DroneData, LeavesData};
+use bevy::prelude::*;
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
+use std::fmt::Display;
+use wg_2024::network::NodeId;
+
+// One frame is recorded every second, an hour of history is kept
+const MAX_FRAMES: usize = 3600;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NodeKind {
+    Drone,
+    Client,
+    Server,
+}
+
+impl Display for NodeKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NodeKind::Drone => write!(f, "Drone"),
+            NodeKind::Client => write!(f, "Client"),
+            NodeKind::Server => write!(f, "Server"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct NodeRecord {
+    pub id: NodeId,
+    pub kind: NodeKind,
+    pub name_impl: String,
+    pub pdr: Option<f32>,
+    pub position: Vec2,
+    pub neighbours: Vec<NodeId>,
+}
+
+// The topology and the statistics at one moment, leaf messages are not kept
+#[derive(Debug, Clone)]
+pub struct Frame {
+    pub time: f32,
+    pub nodes: Vec<NodeRecord>,
+    pub drone: HashMap<NodeId, DroneData>,
+    pub leaf: HashMap<NodeId, LeavesData>,
+}
+
+impl Frame {
+    pub fn node(&self, id: NodeId) -> Option<&NodeRecord> {
+        self.nodes.iter().find(|node| node.id == id)
+    }
+
+    pub fn edges(&self) -> BTreeSet<(NodeId, NodeId)> {
+        self.nodes
+            .iter()
+            .flat_map(|node| {
+                node.neighbours
+                    .iter()
+                    .map(move |ngb| (node.id.min(*ngb), node.id.max(*ngb)))
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MarkKind {
+    Crash,
+    EdgeAdded,
+    EdgeRemoved,
+    Alert,
+}
+
+#[derive(Debug, Clone)]
+pub struct Mark {
+    pub time: f32,
+    pub kind: MarkKind,
+    pub text: String,
+}
+
+#[derive(Resource, Default)]
+pub struct Timeline {
+    pub frames: VecDeque<Frame>,
+    pub marks: Vec<Mark>,
+    // Index of the frame being looked at, none when live
+    pub viewing: Option<usize>,
+    // Nodes with a queue alarm at the last frame
+    alarmed: HashSet<NodeId>,
+}
+
+impl Timeline {
+    pub fn last_time(&self) -> Option<f32> {
+        self.frames.back().map(|frame| frame.time)
+    }
+
+    pub fn viewed(&self) -> Option<&Frame> {
+        self.frames.get(self.viewing?)
+    }
+
+    pub fn mark(&mut self, time: f32, kind: MarkKind, text: String) {
+        self.marks.push(Mark { time, kind, text });
+    }
+
+    /// Marks the nodes that got a queue alarm since the last frame.
+    pub fn mark_alarms(&mut self, time: f32, alarmed: HashSet<NodeId>) {
+        for id in alarmed.difference(&self.alarmed) {
+            self.marks.push(Mark {
+                time,
+                kind: MarkKind::Alert,
+                text: format!("Queue alarm on drone {id}"),
+            });
+        }
+        self.alarmed = alarmed;
+    }
+
+    /// Stores a new frame, marking the crashes and the edge changes since the previous one.
+    pub fn push(&mut self, frame: Frame) {
+        if let Some(last) = self.frames.back() {
+            for node in &last.nodes {
+                if frame.node(node.id).is_none() {
+                    self.marks.push(Mark {
+                        time: frame.time,
+                        kind: MarkKind::Crash,
+                        text: format!("{} {} removed", node.kind, node.id),
+                    });
+                }
+            }
+            let (before, after) = (last.edges(), frame.edges());
+            for (a, b) in after.difference(&before) {
+                self.marks.push(Mark {
+                    time: frame.time,
+                    kind: MarkKind::EdgeAdded,
+                    text: format!("Edge {a} - {b} added"),
+                });
+            }
+            for (a, b) in before.difference(&after) {
+                // Edges of a removed node are part of its crash
+                if frame.node(*a).is_some() && frame.node(*b).is_some() {
+                    self.marks.push(Mark {
+                        time: frame.time,
+                        kind: MarkKind::EdgeRemoved,
+                        text: format!("Edge {a} - {b} removed"),
+                    });
+                }
+            }
+        }
+
+        self.frames.push_back(frame);
+        if self.frames.len() > MAX_FRAMES {
+            self.frames.pop_front();
+            self.viewing = self.viewing.map(|i| i.saturating_sub(1));
+            if let Some(first) = self.frames.front() {
+                let start = first.time;
+                self.marks.retain(|mark| mark.time >= start);
+            }
+        }
+    }
+}
@@ -0,0 +1,271 @@
+use super::resources::{Frame, MarkKind, NodeKind, NodeRecord, Timeline};
+use crate::components::{Drone, Edge, Leaf, LeafType, Node, Text};
+use crate::core::creator::EDGE_COLOR;
+use crate::event_listener::resources::LeavesData;
+use crate::event_listener::DisplayedInfo;
+use crate::invariants::InvariantsState;
+use crate::protocol::ProtocolMonitor;
+use crate::queues::QueueMonitor;
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+use std::collections::HashMap;
+
+// Radius of the circles drawn in place of the nodes when looking at the past
+const NODE_RADIUS: f32 = 20.0;
+
+pub fn record_frame(
+    mut timeline: ResMut<Timeline>,
+    nodes: Query<(&Node, &Transform, Option<&Drone>, Option<&Leaf>)>,
+    info: Res<DisplayedInfo>,
+    protocol: Res<ProtocolMonitor>,
+    queues: Res<QueueMonitor>,
+    invariants: Res<InvariantsState>,
+    time: Res<Time<Virtual>>,
+) {
+    let now = time.elapsed_secs();
+    let since = timeline.last_time().unwrap_or(f32::NEG_INFINITY);
+    for alert in protocol.alerts.iter().filter(|alert| alert.time > since) {
+        timeline.mark(
+            alert.time,
+            MarkKind::Alert,
+            format!("{}: {}", alert.kind, alert.description),
+        );
+    }
+    for violation in invariants
+        .violations
+        .iter()
+        .filter(|violation| violation.time > since)
+    {
+        timeline.mark(
+            violation.time,
+            MarkKind::Alert,
+            format!("Invariant violated: {}", violation.invariant),
+        );
+    }
+    timeline.mark_alarms(
+        now,
+        queues.alarms.iter().map(|alarm| alarm.node_id).collect(),
+    );
+
+    let mut records: Vec<NodeRecord> = nodes
+        .iter()
+        .map(|(node, transform, drone, leaf)| {
+            let kind = match leaf.map(|leaf| &leaf.leaf_type) {
+                None => NodeKind::Drone,
+                Some(LeafType::Client) => NodeKind::Client,
+                Some(LeafType::Server) => NodeKind::Server,
+            };
+            let mut neighbours: Vec<_> = node.neighbours.iter().copied().collect();
+            neighbours.sort_unstable();
+            NodeRecord {
+                id: node.id,
+                kind,
+                name_impl: node.name_impl.clone(),
+                pdr: drone.map(|drone| drone.pdr),
+                position: transform.translation.truncate(),
+                neighbours,
+            }
+        })
+        .collect();
+    records.sort_by_key(|record| record.id);
+
+    let leaf = info
+        .leaf
+        .iter()
+        .map(|(id, data)| {
+            let data = LeavesData {
+                packets_sent: data.packets_sent,
+                data_sent: data.data_sent.clone(),
+                msg_n: data.msg_n,
+                neighbours: data.neighbours.clone(),
+                messages: HashMap::new(),
+                sessions_started: data.sessions_started.clone(),
+                packet_types: data.packet_types.clone(),
+            };
+            (*id, data)
+        })
+        .collect();
+    timeline.push(Frame {
+        time: now,
+        nodes: records,
+        drone: info.drone.clone(),
+        leaf,
+    });
+}
+
+fn mark_color(kind: MarkKind) -> egui::Color32 {
+    match kind {
+        MarkKind::Crash => egui::Color32::RED,
+        MarkKind::EdgeAdded => egui::Color32::GREEN,
+        MarkKind::EdgeRemoved => egui::Color32::from_rgb(255, 140, 0),
+        MarkKind::Alert => egui::Color32::YELLOW,
+    }
+}
+
+pub fn timeline_bar(mut contexts: EguiContexts, mut timeline: ResMut<Timeline>) {
+    egui::TopBottomPanel::bottom("Timeline").show(contexts.ctx_mut(), |ui| {
+        let (Some(first), Some(last)) = (
+            timeline.frames.front().map(|frame| frame.time),
+            timeline.last_time(),
+        ) else {
+            ui.label("Timeline: nothing recorded yet");
+            return;
+        };
+        ui.horizontal(|ui| {
+            match timeline.viewed().map(|frame| frame.time) {
+                Some(time) => {
+                    ui.colored_label(
+                        egui::Color32::YELLOW,
+                        format!("Viewing the network at {time:.0}s, read-only"),
+                    );
+                    if ui.button("Back to live").clicked() {
+                        timeline.viewing = None;
+                    }
+                }
+                None => {
+                    ui.label(format!("Live, {:.0}s recorded", last - first));
+                }
+            }
+            ui.separator();
+            for (kind, name) in [
+                (MarkKind::Crash, "Crash"),
+                (MarkKind::EdgeAdded, "Edge added"),
+                (MarkKind::EdgeRemoved, "Edge removed"),
+                (MarkKind::Alert, "Alert"),
+            ] {
+                ui.colored_label(mark_color(kind), name);
+            }
+        });
+
+        let (rect, response) = ui.allocate_exact_size(
+            egui::vec2(ui.available_width(), 24.0),
+            egui::Sense::click_and_drag(),
+        );
+        let span = (last - first).max(1.0);
+        let x_of = |time: f32| rect.left() + rect.width() * (time - first) / span;
+        let painter = ui.painter();
+        painter.rect_filled(rect, 2.0, egui::Color32::from_gray(40));
+        for mark in &timeline.marks {
+            let x = x_of(mark.time);
+            painter.line_segment(
+                [egui::pos2(x, rect.top()), egui::pos2(x, rect.bottom())],
+                egui::Stroke::new(2.0, mark_color(mark.kind)),
+            );
+        }
+        if let Some(frame) = timeline.viewed() {
+            let x = x_of(frame.time);
+            painter.line_segment(
+                [egui::pos2(x, rect.top()), egui::pos2(x, rect.bottom())],
+                egui::Stroke::new(3.0, egui::Color32::WHITE),
+            );
+        }
+
+        if response.clicked() || response.dragged() {
+            if let Some(pos) = response.interact_pointer_pos() {
+                let time = first + (pos.x - rect.left()) / rect.width() * span;
+                let index = timeline.frames.partition_point(|frame| frame.time < time);
+                timeline.viewing = Some(index.min(timeline.frames.len() - 1));
+            }
+        }
+        if let Some(pos) = response.hover_pos() {
+            let texts: Vec<String> = timeline
+                .marks
+                .iter()
+                .filter(|mark| (x_of(mark.time) - pos.x).abs() <= 4.0)
+                .map(|mark| format!("{:.0}s: {}", mark.time, mark.text))
+                .collect();
+            if !texts.is_empty() {
+                response.on_hover_text(texts.join("\n"));
+            }
+        }
+    });
+}
+
+pub fn history_panel(mut contexts: EguiContexts, timeline: Res<Timeline>) {
+    let Some(frame) = timeline.viewed() else {
+        return;
+    };
+    egui::SidePanel::right("History")
+        .resizable(false)
+        .min_width(400.0)
+        .max_width(400.0)
+        .show(contexts.ctx_mut(), |ui| {
+            ui.heading(format!("Network at {:.0}s", frame.time));
+            ui.label("Read-only, go back to live to change the network");
+            ui.separator();
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                egui::Grid::new("history_grid")
+                    .striped(true)
+                    .show(ui, |ui| {
+                        ui.label("Node");
+                        ui.label("Type");
+                        ui.label("PDR");
+                        ui.label("Sent");
+                        ui.label("Data sent");
+                        ui.label("Dropped");
+                        ui.label("Neighbours");
+                        ui.end_row();
+                        for node in &frame.nodes {
+                            ui.label(format!("{}: {}", node.name_impl, node.id));
+                            ui.label(node.kind.to_string());
+                            ui.label(node.pdr.map_or("-".to_string(), |pdr| format!("{pdr:.2}")));
+                            if let Some(data) = frame.drone.get(&node.id) {
+                                ui.label(data.packets_sent.to_string());
+                                ui.label(data.data_sent.to_string());
+                                ui.label(data.fragments_dropped.to_string());
+                            } else if let Some(data) = frame.leaf.get(&node.id) {
+                                ui.label(data.packets_sent.to_string());
+                                ui.label(data.data_sent.to_string());
+                                ui.label("-");
+                            } else {
+                                ui.label("0");
+                                ui.label("0");
+                                ui.label("-");
+                            }
+                            let neighbours: Vec<String> =
+                                node.neighbours.iter().map(|id| id.to_string()).collect();
+                            ui.label(neighbours.join(", "));
+                            ui.end_row();
+                        }
+                    });
+            });
+        });
+}
+
+pub fn draw_history(timeline: Res<Timeline>, mut gizmos: Gizmos) {
+    let Some(frame) = timeline.viewed() else {
+        return;
+    };
+    for (a, b) in frame.edges() {
+        if let (Some(a), Some(b)) = (frame.node(a), frame.node(b)) {
+            gizmos.line_2d(a.position, b.position, EDGE_COLOR);
+        }
+    }
+    for node in &frame.nodes {
+        let color = match node.kind {
+            // From green to red as the PDR grows
+            NodeKind::Drone => {
+                let pdr = node.pdr.unwrap_or_default();
+                Color::srgb(pdr, 1.0 - pdr, 0.0)
+            }
+            NodeKind::Client => LeafType::Client.color(),
+            NodeKind::Server => LeafType::Server.color(),
+        };
+        gizmos.circle_2d(node.position, NODE_RADIUS, color);
+    }
+}
+
+// The live graph is hidden while looking at the past, the recorded one is drawn instead
+pub fn sync_live_visibility(
+    timeline: Res<Timeline>,
+    mut query: Query<&mut Visibility, Or<(With<Node>, With<Edge>, With<Text>)>>,
+) {
+    let visibility = if timeline.viewing.is_some() {
+        Visibility::Hidden
+    } else {
+        Visibility::Inherited
+    };
+    for mut current in &mut query {
+        current.set_if_neq(visibility);
+    }
+}
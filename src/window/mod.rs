@@ -5,15 +5,22 @@
 mod resources;
 mod systems;
 
+use crate::events::SelectNodeEvent;
+use crate::timeline::timeline_live;
 use bevy::prelude::*;
-use systems::{initialize_ui_state, window};
+use resources::BoxSelection;
+use systems::{box_select, draw_selection, initialize_ui_state, select_node, window};
 pub use systems::{observer_drone, observer_leaf};
 
 pub struct WindowPlugin;
 
 impl Plugin for WindowPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Startup, initialize_ui_state)
-            .add_systems(Update, window);
+        app.add_event::<SelectNodeEvent>()
+            .insert_resource(BoxSelection::default())
+            .add_systems(Startup, initialize_ui_state)
+            .add_systems(Update, (select_node, draw_selection))
+            // The bulk actions are in the side panel, only shown on the live state
+            .add_systems(Update, (box_select, window).run_if(timeline_live));
     }
 }
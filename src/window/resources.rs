@@ -13,3 +13,9 @@ pub struct SelectedUiState {
     pub node_to_add: Option<String>,
     pub node_to_rmv: Option<String>,
 }
+
+#[derive(Resource, Debug, Default)]
+pub struct BoxSelection {
+    // Where the drag started, in world coordinates
+    pub start: Option<Vec2>,
+}
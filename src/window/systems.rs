@@ -1,16 +1,25 @@
 use std::cmp::min;
 
-use super::resources::{MainUiState, SelectedUiState};
+use super::resources::{BoxSelection, MainUiState, SelectedUiState};
 use crate::components::{
     CrashMarker, Drone, Leaf,
     LeafType::{Client, Server},
-    Node, SelectedMarker,
+    Node, SelectedMarker, SelectionSpriteMarker,
 };
-use crate::event_listener::resources::Bytes;
+use crate::event_listener::resources::{Bytes, PacketTypeCounters};
 use crate::event_listener::DisplayedInfo;
-use crate::events::{AddDroneEvent, AddEdgeEvent, RmvEdgeEvent};
+use crate::events::{AddDroneEvent, AddEdgeEvent, RmvEdgeEvent, SelectNodeEvent};
+use crate::export::write_export;
+use bevy::picking::{focus::HoverMap, pointer::PointerId};
 use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
 use bevy_egui::{egui, EguiContexts};
+use wg_2024::network::NodeId;
+
+const SELECTION_COLOR: Color = Color::srgb(1.0, 0.8, 0.8);
+const SELECTION_RADIUS: f32 = 30.0;
+// Smaller rectangles, in world units, are taken as clicks
+const BOX_MIN_SIZE: f32 = 5.0;
 
 pub fn initialize_ui_state(mut commands: Commands) {
     commands.insert_resource(MainUiState {
@@ -27,56 +36,134 @@ pub fn initialize_ui_state(mut commands: Commands) {
 
 pub fn observer_drone(
     trigger: Trigger<Pointer<Click>>,
+    keys: Res<ButtonInput<KeyCode>>,
+    mut ew_select: EventWriter<SelectNodeEvent>,
+) {
+    ew_select.send(SelectNodeEvent {
+        entity: trigger.entity(),
+        additive: keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]),
+    });
+}
+
+pub fn observer_leaf(
+    trigger: Trigger<Pointer<Click>>,
+    keys: Res<ButtonInput<KeyCode>>,
+    mut ew_select: EventWriter<SelectNodeEvent>,
+) {
+    ew_select.send(SelectNodeEvent {
+        entity: trigger.entity(),
+        additive: keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]),
+    });
+}
+
+pub fn select_node(
+    mut er_select: EventReader<SelectNodeEvent>,
     mut commands: Commands,
     last_selected_node_query: Query<Entity, With<SelectedMarker>>,
-    mut to_select_node_query: Query<(&mut Node, &Drone, &Transform), Without<SelectedMarker>>,
-    mut selector_query: Query<(&mut Transform, &mut Visibility), (Without<Node>, Without<Camera>)>,
+    to_select_node_query: Query<(&Node, Option<&Drone>, &Transform)>,
+    mut selector_query: Query<
+        (&mut Transform, &mut Visibility),
+        (With<SelectionSpriteMarker>, Without<Node>),
+    >,
     mut selected_state: ResMut<SelectedUiState>,
 ) {
-    let entity = trigger.entity();
-
-    for entity in last_selected_node_query.iter() {
-        commands.entity(entity).remove::<SelectedMarker>();
-    }
-    for (node, drone, transform) in &mut to_select_node_query {
-        if node.entity_id == entity {
-            selected_state.pdr = Some(drone.pdr.clone().to_string());
-            commands.entity(entity).insert(SelectedMarker);
-            for (mut selector, mut visibility) in &mut selector_query {
-                selector.translation =
-                    Vec3::new(transform.translation.x, transform.translation.y, -10.0);
-                *visibility = Visibility::Visible;
+    for event in er_select.read() {
+        let Ok((node, drone, transform)) = to_select_node_query.get(event.entity) else {
+            continue;
+        };
+        if event.additive {
+            // Shift-click on a selected node takes it out of the selection
+            if last_selected_node_query.contains(event.entity) {
+                commands.entity(event.entity).remove::<SelectedMarker>();
+                continue;
             }
+        } else {
+            for entity in last_selected_node_query.iter() {
+                commands.entity(entity).remove::<SelectedMarker>();
+            }
+        }
+        if let Some(drone) = drone {
+            selected_state.pdr = Some(drone.pdr.to_string());
+        } else if !event.additive {
+            selected_state.pdr = None;
+        }
+        commands.entity(node.entity_id).insert(SelectedMarker);
+        for (mut selector, mut visibility) in &mut selector_query {
+            selector.translation =
+                Vec3::new(transform.translation.x, transform.translation.y, -10.0);
+            *visibility = Visibility::Visible;
         }
     }
 }
 
-pub fn observer_leaf(
-    trigger: Trigger<Pointer<Click>>,
+// Dragging on the empty canvas selects every node inside the rectangle, with shift they are added
+pub fn box_select(
     mut commands: Commands,
-    last_selected_node_query: Query<Entity, With<SelectedMarker>>,
-    mut to_select_node_query: Query<(&mut Node, &Transform), Without<SelectedMarker>>,
-    mut selector_query: Query<(&mut Transform, &mut Visibility), (Without<Node>, Without<Camera>)>,
-    mut selected_state: ResMut<SelectedUiState>,
+    mut contexts: EguiContexts,
+    mut selection: ResMut<BoxSelection>,
+    buttons: Res<ButtonInput<MouseButton>>,
+    keys: Res<ButtonInput<KeyCode>>,
+    hover_map: Res<HoverMap>,
+    window: Single<&Window, With<PrimaryWindow>>,
+    camera: Single<(&Camera, &GlobalTransform)>,
+    nodes: Query<(Entity, &Transform, Has<SelectedMarker>), With<Node>>,
+    mut gizmos: Gizmos,
 ) {
-    let entity = trigger.entity();
-
-    for entity in last_selected_node_query.iter() {
-        commands.entity(entity).remove::<SelectedMarker>();
+    let (camera, camera_transform) = *camera;
+    let Some(cursor) = window
+        .cursor_position()
+        .and_then(|pos| camera.viewport_to_world_2d(camera_transform, pos).ok())
+    else {
+        return;
+    };
+    if buttons.just_pressed(MouseButton::Left) {
+        let over_ui = contexts.ctx_mut().is_pointer_over_area();
+        let over_node = hover_map
+            .get(&PointerId::Mouse)
+            .is_some_and(|hits| hits.keys().any(|entity| nodes.contains(*entity)));
+        selection.start = (!over_ui && !over_node).then_some(cursor);
     }
-    for (node, transform) in &mut to_select_node_query {
-        if node.entity_id == entity {
-            selected_state.pdr = None;
+    let Some(start) = selection.start else {
+        return;
+    };
+    let rect = Rect::from_corners(start, cursor);
+    if buttons.pressed(MouseButton::Left) {
+        gizmos.rect_2d(rect.center(), rect.size(), SELECTION_COLOR);
+        return;
+    }
+    selection.start = None;
+    // A simple click on the canvas is not a selection
+    if rect.width() < BOX_MIN_SIZE && rect.height() < BOX_MIN_SIZE {
+        return;
+    }
+    let additive = keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+    for (entity, transform, selected) in nodes.iter() {
+        let inside = rect.contains(transform.translation.truncate());
+        if inside && !selected {
             commands.entity(entity).insert(SelectedMarker);
-            for (mut selector, mut visibility) in &mut selector_query {
-                selector.translation =
-                    Vec3::new(transform.translation.x, transform.translation.y, -10.0);
-                *visibility = Visibility::Visible;
-            }
+        } else if !inside && selected && !additive {
+            commands.entity(entity).remove::<SelectedMarker>();
         }
     }
 }
 
+// The selection sprite only follows a single node, many selected nodes get a ring each
+pub fn draw_selection(
+    selected: Query<&Transform, (With<SelectedMarker>, With<Node>)>,
+    mut gizmos: Gizmos,
+) {
+    if selected.iter().count() < 2 {
+        return;
+    }
+    for transform in selected.iter() {
+        gizmos.circle_2d(
+            transform.translation.truncate(),
+            SELECTION_RADIUS,
+            SELECTION_COLOR,
+        );
+    }
+}
+
 pub fn window(
     mut commands: Commands,
     mut ew_add_drone: EventWriter<AddDroneEvent>,
@@ -171,6 +258,7 @@ pub fn window(
                                         ew_add_drone.send(AddDroneEvent {
                                             pdr,
                                             ngbs: vec![nghb_1, nghb_2],
+                                            position: None,
                                         });
                                     }
                                 }
@@ -190,8 +278,20 @@ pub fn window(
                     .show(ui, |ui| {
                         ui.add_space(10.0);
 
+                        // SEVERAL NODES SELECTED
+                        if query_drone.iter().count() + query_leaf.iter().count() > 1 {
+                            bulk_actions_ui(
+                                ui,
+                                &mut commands,
+                                &mut query_drone,
+                                &query_leaf,
+                                &mut selected_state,
+                                &info,
+                                &mut ew_add_edge,
+                                &mut ew_rmv_edge,
+                            );
                         // SELECTED NODE IS DRONE
-                        if query_drone.iter().count() > 0 {
+                        } else if query_drone.iter().count() > 0 {
                             for (entity, node, mut drone) in &mut query_drone {
                                 ui.with_layout(egui::Layout::top_down(egui::Align::Center), |ui| {
                                     ui.heading(format!("Drone with id: {:?}", node.id));
@@ -279,6 +379,11 @@ pub fn window(
                                         }
                                     }
                                 });
+                                ui.separator();
+                                packet_types_ui(
+                                    ui,
+                                    info.drone.get(&node.id).map(|d| &d.packet_types),
+                                );
                                 ui.add_space(10.0);
                                 // Drone info end
 
@@ -410,6 +515,11 @@ pub fn window(
                                         }
                                     });
                                     ui.separator();
+                                    packet_types_ui(
+                                        ui,
+                                        info.leaf.get(&node.id).map(|l| &l.packet_types),
+                                    );
+                                    ui.separator();
                                     ui.heading("Last messages:");
                                     egui::ScrollArea::vertical()
                                         .max_height(600.0)
@@ -505,6 +615,11 @@ pub fn window(
                                         }
                                     });
                                     ui.separator();
+                                    packet_types_ui(
+                                        ui,
+                                        info.leaf.get(&node.id).map(|l| &l.packet_types),
+                                    );
+                                    ui.separator();
                                     ui.heading("Last messages:");
                                     egui::ScrollArea::vertical()
                                         .max_height(600.0)
@@ -554,3 +669,166 @@ pub fn window(
             });
         });
 }
+
+fn bulk_actions_ui(
+    ui: &mut egui::Ui,
+    commands: &mut Commands,
+    query_drone: &mut Query<(Entity, &Node, &mut Drone), (With<SelectedMarker>, Without<Leaf>)>,
+    query_leaf: &Query<(&Node, &Leaf), (With<SelectedMarker>, Without<Drone>)>,
+    selected_state: &mut SelectedUiState,
+    info: &DisplayedInfo,
+    ew_add_edge: &mut EventWriter<AddEdgeEvent>,
+    ew_rmv_edge: &mut EventWriter<RmvEdgeEvent>,
+) {
+    let mut selected: Vec<&Node> = query_drone
+        .iter()
+        .map(|(_, node, _)| node)
+        .chain(query_leaf.iter().map(|(node, _)| node))
+        .collect();
+    selected.sort_by_key(|node| node.id);
+    let ids: Vec<NodeId> = selected.iter().map(|node| node.id).collect();
+    let pairs: Vec<(NodeId, NodeId, bool)> = selected
+        .iter()
+        .enumerate()
+        .flat_map(|(i, a)| {
+            selected[i + 1..]
+                .iter()
+                .map(move |b| (a.id, b.id, a.neighbours.contains(&b.id)))
+        })
+        .collect();
+
+    ui.with_layout(egui::Layout::top_down(egui::Align::Center), |ui| {
+        ui.heading(format!("{} nodes selected", ids.len()));
+        ui.add_space(10.0);
+    });
+    ui.label(format!("Nodes: {ids:?}"));
+    ui.label("Shift-click a node to add or remove it, drag on the canvas to select an area");
+    ui.separator();
+    ui.heading("Actions:");
+    ui.add_space(10.0);
+
+    let mut set_pdr = None;
+    ui.horizontal(|ui| {
+        ui.label("PDR of the selected drones:");
+        ui.add_sized(
+            [60.0, 20.0],
+            egui::TextEdit::singleline(selected_state.pdr.get_or_insert_with(|| 0.0.to_string())),
+        );
+        if ui.button("Update").clicked() {
+            set_pdr = selected_state
+                .pdr
+                .as_ref()
+                .and_then(|pdr_s| pdr_s.parse::<f32>().ok())
+                .filter(|pdr| (0.0..=1.0).contains(pdr));
+        }
+    });
+    ui.add_space(10.0);
+    ui.horizontal(|ui| {
+        if ui.button("Connect them to each other").clicked() {
+            let missing = pairs.iter().filter(|(_, _, connected)| !connected);
+            for &(start_node, end_node, _) in missing {
+                ew_add_edge.send(AddEdgeEvent {
+                    start_node,
+                    end_node,
+                });
+            }
+        }
+        if ui.button("Remove the edges between them").clicked() {
+            let existing = pairs.iter().filter(|(_, _, connected)| *connected);
+            for &(start_node, end_node, _) in existing {
+                ew_rmv_edge.send(RmvEdgeEvent {
+                    start_node,
+                    end_node,
+                });
+            }
+        }
+    });
+    ui.add_space(10.0);
+    if ui.button("Export the selection stats").clicked() {
+        let selection = DisplayedInfo {
+            drone: info
+                .drone
+                .iter()
+                .filter(|(id, _)| ids.contains(id))
+                .map(|(id, data)| (*id, data.clone()))
+                .collect(),
+            leaf: info
+                .leaf
+                .iter()
+                .filter(|(id, _)| ids.contains(id))
+                .map(|(id, data)| (*id, data.clone()))
+                .collect(),
+        };
+        match write_export(&selection) {
+            Ok(dir) => println!("Selection stats exported to {}", dir.display()),
+            Err(err) => eprintln!("Error exporting selection stats: {err}"),
+        }
+    }
+    ui.with_layout(egui::Layout::bottom_up(egui::Align::Center), |ui| {
+        if ui
+            .add_sized(
+                [160.0, 40.0],
+                egui::Button::new("Crash the selected drones").fill(egui::Color32::DARK_RED),
+            )
+            .clicked()
+        {
+            // The crash system checks the connectivity without all of them at once
+            for (entity, node, _) in query_drone.iter() {
+                println!("Trying to crash the drone (id): {:?}", node.id);
+                commands.entity(entity).insert(CrashMarker);
+            }
+        }
+    });
+
+    if let Some(pdr) = set_pdr {
+        for (_, node, mut drone) in query_drone.iter_mut() {
+            if drone.set_packet_drop_rate(pdr).is_err() {
+                eprintln!("Error setting the PDR of drone {}", node.id);
+            }
+        }
+        println!("New PDR for the selected drones: {pdr}");
+    }
+}
+
+fn packet_types_ui(ui: &mut egui::Ui, counters: Option<&PacketTypeCounters>) {
+    let counters = counters.cloned().unwrap_or_default();
+    ui.horizontal(|ui| {
+        ui.add_space(6.0);
+        ui.label("Packets by type:");
+    });
+    ui.horizontal(|ui| {
+        ui.add_space(6.0);
+        ui.label(format!("Fragments: {}", counters.fragments));
+        ui.add_space(20.0);
+        ui.label(format!("Acks: {}", counters.acks));
+        ui.add_space(20.0);
+        ui.label(format!("Nacks: {}", counters.nacks()));
+    });
+    ui.horizontal(|ui| {
+        ui.add_space(6.0);
+        ui.label(format!(
+            "Nacks ErrorInRouting: {}",
+            counters.nacks_error_in_routing
+        ));
+        ui.add_space(20.0);
+        ui.label(format!(
+            "DestinationIsDrone: {}",
+            counters.nacks_destination_is_drone
+        ));
+    });
+    ui.horizontal(|ui| {
+        ui.add_space(6.0);
+        ui.label(format!("Nacks Dropped: {}", counters.nacks_dropped));
+        ui.add_space(20.0);
+        ui.label(format!(
+            "UnexpectedRecipient: {}",
+            counters.nacks_unexpected_recipient
+        ));
+    });
+    ui.horizontal(|ui| {
+        ui.add_space(6.0);
+        ui.label(format!("Flood requests: {}", counters.flood_requests));
+        ui.add_space(20.0);
+        ui.label(format!("Flood responses: {}", counters.flood_responses));
+    });
+}